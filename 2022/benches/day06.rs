@@ -0,0 +1,46 @@
+extern crate advent_of_code_2022;
+extern crate clap;
+#[macro_use]
+extern crate criterion;
+#[cfg(test)]
+extern crate proptest;
+
+use criterion::{black_box, Criterion};
+
+/// The solutions are binaries, so their source is pulled in as a module rather than linked as a
+/// library.
+mod day06 {
+    #![allow(dead_code, unused_imports)]
+
+    pub const INPUT: &str = include_str!("../puzzles/day06.prod");
+
+    pub fn first_marker_hash_set<const N: usize>(stream: &str) -> Option<usize> {
+        find_first_marker_with::<N>(stream, has_duplicates)
+    }
+
+    pub fn first_marker_bitmask<const N: usize>(stream: &str) -> Option<usize> {
+        find_first_marker_with::<N>(stream, has_duplicates_bitmask)
+    }
+
+    include!("../src/bin/day06.rs");
+}
+
+fn first_marker(c: &mut Criterion) {
+    let mut group = c.benchmark_group("first_marker");
+    group.bench_function("hash_set/4", |b| {
+        b.iter(|| day06::first_marker_hash_set::<4>(black_box(day06::INPUT)))
+    });
+    group.bench_function("bitmask/4", |b| {
+        b.iter(|| day06::first_marker_bitmask::<4>(black_box(day06::INPUT)))
+    });
+    group.bench_function("hash_set/14", |b| {
+        b.iter(|| day06::first_marker_hash_set::<14>(black_box(day06::INPUT)))
+    });
+    group.bench_function("bitmask/14", |b| {
+        b.iter(|| day06::first_marker_bitmask::<14>(black_box(day06::INPUT)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, first_marker);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+extern crate advent_of_code_2022;
+extern crate anyhow;
+extern crate clap;
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, Criterion};
+
+/// The solutions are binaries, so their source is pulled in as a module rather than linked as a
+/// library.
+mod day08 {
+    #![allow(dead_code, unused_imports)]
+
+    pub const INPUT: &str = include_str!("../puzzles/day08.prod");
+
+    /// The original parser, going through `char`s. Returns the number of trees since `Forest` is
+    /// private.
+    pub fn from_chars(input: &str) -> usize {
+        Forest {
+            trees: input
+                .chars()
+                .filter(|c| *c != '\n')
+                .map(|c| {
+                    assert!(c.is_ascii_digit());
+                    c as u8 - b'0'
+                })
+                .collect(),
+            width: input.lines().take(1).next().unwrap().chars().count(),
+        }
+        .len()
+    }
+
+    pub fn from_bytes(input: &str) -> usize {
+        Forest::from(parse_grid_of_digits(input).unwrap()).len()
+    }
+
+    include!("../src/bin/day08.rs");
+}
+
+fn parse_forest(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_forest");
+    group.bench_function("chars", |b| b.iter(|| day08::from_chars(black_box(day08::INPUT))));
+    group.bench_function("bytes", |b| b.iter(|| day08::from_bytes(black_box(day08::INPUT))));
+    group.finish();
+}
+
+criterion_group!(benches, parse_forest);
+criterion_main!(benches);
@@ -0,0 +1,26 @@
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+
+/// The solutions are binaries, so their source is pulled in as a module rather than linked as a
+/// library. Only the parsers are exercised here.
+mod day05 {
+    #![allow(dead_code)]
+
+    include!("../../src/bin/day05.rs");
+
+    /// Feeds `input` to the puzzle, crate stacks and move command parsers. Errors are expected,
+    /// panics are not.
+    pub fn parse(input: &str) {
+        let _ = input.parse::<Puzzle>();
+        let _ = input.parse::<CrateStacks>();
+        for line in input.lines() {
+            let _ = line.parse::<MoveCommand>();
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    day05::parse(&String::from_utf8_lossy(data));
+});
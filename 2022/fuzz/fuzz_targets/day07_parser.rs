@@ -0,0 +1,22 @@
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+
+/// The solutions are binaries, so their source is pulled in as a module rather than linked as a
+/// library. Only the parser is exercised here.
+mod day07 {
+    #![allow(dead_code)]
+
+    include!("../../src/bin/day07.rs");
+
+    /// Feeds `input` to the shell session parser. Errors are expected, panics are not.
+    pub fn parse(input: &str) {
+        let _ = parse_shell_session_output(input, false);
+        let _ = parse_shell_session_output(input, true);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    day07::parse(&String::from_utf8_lossy(data));
+});
@@ -0,0 +1,102 @@
+//! Counts the heap allocations of a run, to find the allocation hotspots of the solutions.
+//!
+//! With the `allocs` feature, every allocation goes through `CountingAllocator`, which forwards to
+//! the system allocator. Without it, the system allocator is used directly and nothing is counted.
+
+#[cfg(feature = "allocs")]
+use std::alloc::{GlobalAlloc, Layout, System};
+#[cfg(feature = "allocs")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The number of allocations and allocated bytes since the start of the process. Reallocations
+/// count as allocations of their new size, and deallocations are not subtracted.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AllocStats {
+    pub allocations: u64,
+    pub bytes: u64,
+}
+
+#[cfg(feature = "allocs")]
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+#[cfg(feature = "allocs")]
+static BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// A global allocator counting the allocations before forwarding them to the system allocator.
+#[cfg(feature = "allocs")]
+pub struct CountingAllocator;
+
+#[cfg(feature = "allocs")]
+impl CountingAllocator {
+    fn count(size: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(size as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "allocs")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        CountingAllocator::count(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        CountingAllocator::count(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        CountingAllocator::count(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[cfg(feature = "allocs")]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Returns the allocations counted so far.
+#[cfg(feature = "allocs")]
+pub fn stats() -> AllocStats {
+    AllocStats {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        bytes: BYTES.load(Ordering::Relaxed),
+    }
+}
+
+#[cfg(not(feature = "allocs"))]
+pub fn stats() -> AllocStats {
+    AllocStats::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "allocs")]
+    #[test]
+    fn counts_allocations() {
+        let before = stats();
+        let values = std::hint::black_box(vec![0u64; 1024]);
+        let mut strings = vec![];
+        for value in values.iter().take(3) {
+            strings.push(value.to_string());
+        }
+        let after = stats();
+
+        // Other tests may allocate concurrently, so only lower bounds hold.
+        assert!(after.allocations >= before.allocations + 4);
+        assert!(after.bytes >= before.bytes + 8 * 1024);
+    }
+
+    #[cfg(not(feature = "allocs"))]
+    #[test]
+    fn counts_nothing() {
+        let _values = std::hint::black_box(vec![0u64; 1024]);
+        assert_eq!(stats(), AllocStats::default());
+    }
+}
@@ -0,0 +1,265 @@
+extern crate advent_of_code_2022;
+extern crate anyhow;
+extern crate clap;
+extern crate serde_json;
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+#[cfg(feature = "download")]
+use advent_of_code_2022::download;
+#[cfg(feature = "gen")]
+use advent_of_code_2022::generate;
+use advent_of_code_2022::input::{self, InputKind};
+use advent_of_code_2022::pool;
+use advent_of_code_2022::runner::SolveReport;
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand};
+
+#[derive(Subcommand)]
+enum Command {
+    /// Downloads the puzzle input of a day to `puzzles/dayNN.prod`, unless it already exists.
+    ///
+    /// Requires the `download` feature, and the session cookie in `AOC_SESSION`.
+    Fetch {
+        // The day of the puzzle.
+        #[clap(value_parser = clap::value_parser!(u32).range(1..=25))]
+        day: u32,
+
+        // The year of the puzzle. Defaults to the year of this crate.
+        #[clap(short = 'y', long = "year", default_value_t = 2022)]
+        year: u32,
+    },
+    /// Runs the solution of every day, and prints their answers in day order.
+    ///
+    /// The solutions are run from the binaries next to this one, so they must be built first, eg.
+    /// with `cargo build --bins`.
+    RunAll {
+        // The number of solutions to run concurrently.
+        #[clap(short = 'j', long = "jobs", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        jobs: u64,
+
+        // Print a `day,part,answer,micros` CSV row per part instead of the answers, timing the
+        // parts alone. Running them one job at a time gives steadier timings.
+        #[clap(long = "parts-timing-csv")]
+        parts_timing_csv: bool,
+    },
+    /// Prints a random puzzle input for a day, which its solution parses without error.
+    ///
+    /// Requires the `gen` feature.
+    Generate {
+        // The day of the puzzle.
+        #[clap(value_parser = clap::value_parser!(u8).range(1..=11))]
+        day: u8,
+
+        // The seed of the random generator. The same seed always yields the same input.
+        #[clap(short = 's', long = "seed", default_value_t = 0)]
+        seed: u64,
+
+        // Scales the input: the number of lines, groups, directories, etc. depending on the day.
+        #[clap(short = 'n', long = "size", default_value_t = 100)]
+        size: usize,
+    },
+}
+
+/// The days with a solution.
+const DAYS: [u8; 11] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+#[derive(Parser)]
+struct CmdlineArgs {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[cfg(feature = "download")]
+fn fetch(year: u32, day: u32, path: &Path) -> Result<()> {
+    if download::fetch_input(year, day, path)? {
+        println!("downloaded {}", path.display());
+    } else {
+        println!("{} already exists, skipping", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "download"))]
+fn fetch(_year: u32, _day: u32, _path: &Path) -> Result<()> {
+    Err(anyhow::anyhow!("`fetch` requires the `download` feature"))
+}
+
+#[cfg(feature = "gen")]
+fn generate(day: u8, seed: u64, size: usize) -> Result<()> {
+    print!("{}", generate::generate(day, seed, size)?);
+    Ok(())
+}
+
+#[cfg(not(feature = "gen"))]
+fn generate(_day: u8, _seed: u64, _size: usize) -> Result<()> {
+    Err(anyhow::anyhow!("`generate` requires the `gen` feature"))
+}
+
+/// Runs the solution of `day` from the binary in `bin_dir`, and returns its report.
+fn run_day(bin_dir: &Path, day: u8) -> Result<SolveReport> {
+    let program = bin_dir.join(format!("day{:02}", day));
+    let output = process::Command::new(&program)
+        .args(["--format", "json"])
+        .output()
+        .with_context(|| format!("failed to run {}", program.display()))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} failed: {}",
+            program.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("unexpected output from {}", program.display()))
+}
+
+/// Formats the result of a day as a single paragraph.
+fn describe(day: u8, report: &Result<SolveReport>) -> String {
+    match report {
+        Ok(report) => format!(
+            "Day {:02} ({:.3} ms)\nPart 1: {}\nPart 2: {}",
+            day,
+            report.ms,
+            report.part1.as_deref().unwrap_or("-"),
+            report.part2.as_deref().unwrap_or("-"),
+        ),
+        Err(e) => format!("Day {:02}\nerror: {:#}", day, e),
+    }
+}
+
+/// The header of the CSV printed by `run-all --parts-timing-csv`.
+const CSV_HEADER: &str = "day,part,answer,micros";
+
+/// Quotes `field` if it holds a comma, a quote or a line break, as multi-line answers do.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Formats the CSV rows of the parts answered in `report`.
+fn csv_rows(day: u8, report: &SolveReport) -> Vec<String> {
+    [
+        (1, &report.part1, report.part1_micros),
+        (2, &report.part2, report.part2_micros),
+    ]
+    .iter()
+    .filter_map(|&(part, answer, micros)| {
+        Some(format!("{},{},{},{}", day, part, csv_field(answer.as_deref()?), micros?))
+    })
+    .collect()
+}
+
+fn run_all(jobs: usize, parts_timing_csv: bool) -> Result<()> {
+    let bin_dir: PathBuf = env::current_exe()?
+        .parent()
+        .ok_or_else(|| anyhow!("no directory for the current executable"))?
+        .to_owned();
+    let reports = pool::map_ordered(&DAYS, jobs, |day| run_day(&bin_dir, *day));
+    if parts_timing_csv {
+        println!("{}", CSV_HEADER);
+        for (day, report) in DAYS.iter().zip(&reports) {
+            match report {
+                Ok(solved) => csv_rows(*day, solved)
+                    .iter()
+                    .for_each(|row| println!("{}", row)),
+                Err(_) => eprintln!("{}", describe(*day, report)),
+            }
+        }
+    } else {
+        print_descriptions(&reports);
+    }
+
+    match reports.iter().filter(|report| report.is_err()).count() {
+        0 => Ok(()),
+        failures => Err(anyhow!("{} day(s) failed", failures)),
+    }
+}
+
+/// Prints the result of each day as a paragraph, in day order.
+fn print_descriptions(reports: &[Result<SolveReport>]) {
+    let descriptions = DAYS
+        .iter()
+        .zip(reports)
+        .map(|(day, report)| describe(*day, report))
+        .collect::<Vec<_>>();
+    println!("{}", descriptions.join("\n\n"));
+}
+
+fn main() -> Result<()> {
+    let cmdline_args = CmdlineArgs::parse();
+
+    match cmdline_args.command {
+        Command::Fetch { day, year } => {
+            let path = input::input_path(day as u8, InputKind::Prod);
+            fetch(year, day, &path)
+        }
+        Command::RunAll {
+            jobs,
+            parts_timing_csv,
+        } => run_all(jobs as usize, parts_timing_csv),
+        Command::Generate { day, seed, size } => generate(day, seed, size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(part1: Option<&str>, part2: Option<&str>) -> SolveReport {
+        SolveReport {
+            day: Some(10),
+            part1: part1.map(str::to_owned),
+            part2: part2.map(str::to_owned),
+            ms: 0.25,
+            part1_micros: part1.map(|_| 120),
+            part2_micros: part2.map(|_| 130),
+        }
+    }
+
+    /// Splits a CSV row on the commas outside of quotes.
+    fn split_row(row: &str) -> Vec<String> {
+        let mut fields = vec![String::new()];
+        let mut quoted = false;
+        for c in row.chars() {
+            match c {
+                '"' => quoted = !quoted,
+                ',' if !quoted => fields.push(String::new()),
+                c => fields.last_mut().unwrap().push(c),
+            }
+        }
+        fields
+    }
+
+    #[test]
+    fn csv_header_and_rows() {
+        let rows = csv_rows(4, &report(Some("547"), Some("843")));
+
+        assert_eq!(split_row(CSV_HEADER), ["day", "part", "answer", "micros"]);
+        assert_eq!(rows, ["4,1,547,120", "4,2,843,130"]);
+        for row in rows {
+            let fields = split_row(&row);
+            assert_eq!(fields.len(), 4);
+            assert!(fields[0].parse::<u8>().is_ok());
+            assert!(fields[3].parse::<u64>().is_ok());
+        }
+    }
+
+    #[test]
+    fn csv_rows_skip_parts_not_run() {
+        assert_eq!(csv_rows(6, &report(None, Some("2508"))), ["6,2,2508,130"]);
+        assert!(csv_rows(6, &report(None, None)).is_empty());
+    }
+
+    #[test]
+    fn csv_field_quoting() {
+        assert_eq!(csv_field("QPJPLMNNR"), "QPJPLMNNR");
+        assert_eq!(csv_field("##..\n..##"), "\"##..\n..##\"");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
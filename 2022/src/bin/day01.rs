@@ -1,32 +1,41 @@
+extern crate advent_of_code_2022;
+extern crate anyhow;
 extern crate clap;
-extern crate itertools;
-
+#[cfg(feature = "plot")]
+extern crate textplots;
+
+use advent_of_code_2022::input::InputArgs;
+use advent_of_code_2022::runner::{self, RunnerArgs};
+#[cfg(not(feature = "plot"))]
+use anyhow::anyhow;
+use anyhow::Result;
 use clap::Parser;
-use itertools::Itertools;
 use std::borrow::Borrow;
 use std::cmp;
 use std::fs::File;
 use std::io::{self, BufRead};
-
-#[derive(clap::ValueEnum, Clone)]
-enum ChallengeStage {
-    Stage1,
-    Stage2,
-}
+use std::mem;
 
 #[derive(Parser)]
 struct CmdlineArgs {
-    // The path to the file to read.
-    calorie_ledger_filename: std::path::PathBuf,
+    // The path to the file to read. Defaults to the puzzle input selected by `--sample`/`--prod`.
+    calorie_ledger_filename: Option<std::path::PathBuf>,
+
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    runner: RunnerArgs,
 
-    // The part of the challenge to run. Defaults to the first stage.
-    #[clap(short = 'c', long = "challenge", value_enum, default_value_t = ChallengeStage::Stage1)]
-    challenge: ChallengeStage,
+    // Plot the calorie totals of the elves as a histogram instead of solving the puzzle.
+    #[clap(long = "hist")]
+    hist: bool,
 }
 
 /// An input file consists of a newline-separated list of either:
 ///   - an empty line
 ///   - a positive number
+#[derive(Debug, PartialEq)]
 enum CalorieLedgerToken {
     Newline,
     Number(u64), // `u64` should cover even the fattest of elves…
@@ -37,19 +46,29 @@ enum CalorieLedgerToken {
 /// Implements moderate error tolerance by:
 ///   - ignoring leading and trailing whitespaces on each line
 ///   - ignoring ill-formated calories values
-fn iter_calorie_ledger(calories_ledger: File) -> impl Iterator<Item = CalorieLedgerToken> {
+fn iter_calorie_ledger(
+    calories_ledger: impl io::Read,
+) -> impl Iterator<Item = CalorieLedgerToken> {
     io::BufReader::new(calories_ledger)
         .lines()
-        .filter_map(|line| {
-            let line = line.ok()?;
-            let line = line.trim();
-            if line.is_empty() {
-                Some(CalorieLedgerToken::Newline)
-            } else {
-                let calories = line.parse::<u64>().ok()?;
-                Some(CalorieLedgerToken::Number(calories))
-            }
-        })
+        .filter_map(|line| parse_token(&line.ok()?))
+}
+
+/// Same as `iter_calorie_ledger`, but streams the tokens directly from the lines of an in-memory
+/// `calories_ledger`.
+fn iter_tokens(calories_ledger: &str) -> impl Iterator<Item = CalorieLedgerToken> + '_ {
+    calories_ledger.lines().filter_map(parse_token)
+}
+
+/// Parses a single line of the ledger, or returns `None` if the line is ill-formatted.
+fn parse_token(line: &str) -> Option<CalorieLedgerToken> {
+    let line = line.trim();
+    if line.is_empty() {
+        Some(CalorieLedgerToken::Newline)
+    } else {
+        let calories = line.parse::<u64>().ok()?;
+        Some(CalorieLedgerToken::Number(calories))
+    }
 }
 
 /// The first part of the challenge consists in returning the largest value in the input set.
@@ -74,17 +93,59 @@ fn challenge_stage1(iter: impl Iterator<Item = impl Borrow<CalorieLedgerToken>>)
     cmp::max(acc, max_calories)
 }
 
+/// Splits a stream of `CalorieLedgerToken` into the calorie list of each elf, in the order of the
+/// ledger. Blank lines only separate the lists: leading, trailing or consecutive blank lines don't
+/// yield empty lists.
+fn group_tokens(iter: impl Iterator<Item = impl Borrow<CalorieLedgerToken>>) -> Vec<Vec<u64>> {
+    let mut groups = vec![vec![]];
+    for token in iter {
+        match token.borrow() {
+            CalorieLedgerToken::Newline => {
+                if groups.last().is_some_and(|group: &Vec<u64>| !group.is_empty()) {
+                    groups.push(vec![]);
+                }
+            }
+            CalorieLedgerToken::Number(calories) => groups.last_mut().unwrap().push(*calories),
+        }
+    }
+
+    if groups.last().is_some_and(Vec::is_empty) {
+        groups.pop();
+    }
+    groups
+}
+
+/// Parses the in-memory `calories_ledger` into the calorie list of each elf, with the same error
+/// tolerance as `iter_tokens`. The number of elves is the length of the returned list.
+#[cfg_attr(not(test), allow(dead_code))]
+fn parse_groups(calories_ledger: &str) -> Vec<Vec<u64>> {
+    group_tokens(iter_tokens(calories_ledger))
+}
+
+/// Renders the calorie list of each elf on its own line, eg. `1000 2000 3000`.
+fn render_groups(groups: &[Vec<u64>]) -> String {
+    groups
+        .iter()
+        .map(|group| group.iter().map(u64::to_string).collect::<Vec<_>>().join(" ") + "\n")
+        .collect()
+}
+
 /// Converts a stream of `CalorieLedgerToken` into a stream of calories values.
 fn iter_calories(
     iter: impl Iterator<Item = impl Borrow<CalorieLedgerToken>>,
 ) -> impl Iterator<Item = u64> {
-    iter.batching(|iter| {
-        iter.map_while(|token| match token.borrow() {
-            CalorieLedgerToken::Newline => None,
-            CalorieLedgerToken::Number(calories) => Some(calories.to_owned()),
-        })
-        .sum1()
-    })
+    iter_calories_indexed(iter).map(|(_, calories)| calories)
+}
+
+/// Converts a stream of `CalorieLedgerToken` into a stream of `(elf_index, calories)` pairs, where
+/// elves are numbered from 0 in the order of the ledger.
+fn iter_calories_indexed(
+    iter: impl Iterator<Item = impl Borrow<CalorieLedgerToken>>,
+) -> impl Iterator<Item = (usize, u64)> {
+    group_tokens(iter)
+        .into_iter()
+        .map(|group| group.iter().sum())
+        .enumerate()
 }
 
 /// Keeps the largest N values from the (value, ...n_largest) set.
@@ -101,17 +162,33 @@ fn iter_calories(
 /// assert_eq!(values, [1, 0, 0]);
 /// ```
 fn keep_n_largest<T: PartialOrd, const N: usize>(n_largest: &mut [T; N], value: T) {
+    keep_n_largest_evict(n_largest, value);
+}
+
+/// Same as `keep_n_largest`, but returns the value displaced from `n_largest` by `value`, or `None`
+/// if `n_largest` is unchanged.
+///
+/// ```
+/// let mut values = [0; 2];
+///
+/// assert_eq!(keep_n_largest_evict(&mut values, 1), Some(0));
+/// assert_eq!(keep_n_largest_evict(&mut values, 0), None);
+/// ```
+fn keep_n_largest_evict<T: PartialOrd, const N: usize>(
+    n_largest: &mut [T; N],
+    value: T,
+) -> Option<T> {
     // This is O(n), and works with a `PartialOrd` bound.
     let index_of_min = n_largest
         .iter()
         .enumerate()
         .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(cmp::Ordering::Equal))
-        .map(|(index, _)| index);
+        .map(|(index, _)| index)?;
 
-    if let Some(index_of_min) = index_of_min {
-        if n_largest[index_of_min] < value {
-            n_largest[index_of_min] = value;
-        }
+    if n_largest[index_of_min] < value {
+        Some(mem::replace(&mut n_largest[index_of_min], value))
+    } else {
+        None
     }
 }
 
@@ -132,24 +209,90 @@ fn challenge_n_largest<const N: usize>(
     n_largest.iter().sum()
 }
 
+/// The number of elves in each bucket of calorie totals, the first bucket starting at `start`.
+#[derive(Debug, PartialEq)]
+struct Histogram {
+    start: u64,
+    width: u64,
+    counts: Vec<usize>,
+}
+
+/// Buckets `totals` between their minimum and maximum, using about as many buckets as the square
+/// root of their number. Returns `None` if there are no totals.
+fn histogram(totals: &[u64]) -> Option<Histogram> {
+    let (min, max) = (*totals.iter().min()?, *totals.iter().max()?);
+    let buckets = (totals.len() as f64).sqrt().ceil() as u64;
+    let width = (max - min) / buckets + 1;
+    let mut counts = vec![0; ((max - min) / width + 1) as usize];
+    for total in totals {
+        counts[((total - min) / width) as usize] += 1;
+    }
+
+    Some(Histogram { start: min, width, counts })
+}
+
+/// Prints `histogram` as bars on the terminal.
+#[cfg(feature = "plot")]
+fn plot_histogram(histogram: &Histogram) -> Result<()> {
+    use textplots::{Chart, Plot, Shape};
+
+    let bars = (histogram.start..)
+        .step_by(histogram.width as usize)
+        .zip(&histogram.counts)
+        .map(|(start, count)| (start as f32, *count as f32))
+        .collect::<Vec<_>>();
+    let end = histogram.start + histogram.width * histogram.counts.len() as u64;
+    Chart::new(120, 60, histogram.start as f32, end as f32)
+        .lineplot(&Shape::Bars(&bars))
+        .display();
+    Ok(())
+}
+
+#[cfg(not(feature = "plot"))]
+fn plot_histogram(_histogram: &Histogram) -> Result<()> {
+    Err(anyhow!("`--hist` requires the `plot` feature"))
+}
+
 fn main() -> Result<(), std::io::Error> {
     let cmdline_args = CmdlineArgs::parse();
-    let calorie_ledger =
-        File::open(cmdline_args.calorie_ledger_filename).expect("unable to open input file");
-
-    let iter = iter_calorie_ledger(calorie_ledger);
-    let calories = match cmdline_args.challenge {
-        ChallengeStage::Stage1 => challenge_stage1(iter),
-        ChallengeStage::Stage2 => challenge_n_largest::<3>(iter),
+    let _log = cmdline_args.runner.init_logging();
+    let tokens = match cmdline_args.calorie_ledger_filename {
+        Some(path) => {
+            let calorie_ledger = File::open(path).expect("unable to open input file");
+            iter_calorie_ledger(calorie_ledger).collect::<Vec<_>>()
+        }
+        None => {
+            let calorie_ledger = cmdline_args.input.load(1).expect("failed to read puzzle input");
+            iter_tokens(&calorie_ledger).collect::<Vec<_>>()
+        }
     };
 
-    println!("{calories}");
+    if cmdline_args.runner.print_parsed(|| render_groups(&group_tokens(tokens.iter()))) {
+        return Ok(());
+    }
+
+    if cmdline_args.hist {
+        let totals = iter_calories(tokens.iter()).collect::<Vec<_>>();
+        match histogram(&totals) {
+            Some(histogram) => plot_histogram(&histogram).expect("failed to plot the histogram"),
+            None => println!("No elves"),
+        }
+        return Ok(());
+    }
+
+    runner::run(
+        &cmdline_args.runner,
+        || challenge_stage1(tokens.iter()),
+        || challenge_n_largest::<3>(tokens.iter()),
+    );
+
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::Path;
 
     // Some tests, starting with part 1 of the challenge.
 
@@ -254,6 +397,92 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn iter_calories_indexed_empty() {
+        let mut iter = iter_calories_indexed([].iter());
+
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn iter_calories_indexed_multiple_group() {
+        let input = [
+            CalorieLedgerToken::Number(1),
+            CalorieLedgerToken::Number(2),
+            CalorieLedgerToken::Newline,
+            CalorieLedgerToken::Number(3),
+            CalorieLedgerToken::Newline,
+            CalorieLedgerToken::Number(4),
+            CalorieLedgerToken::Number(5),
+            CalorieLedgerToken::Newline,
+        ];
+        let mut iter = iter_calories_indexed(input.iter());
+
+        assert_eq!(iter.next(), Some((0, 3)));
+        assert_eq!(iter.next(), Some((1, 3)));
+        assert_eq!(iter.next(), Some((2, 9)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn parse_groups_trailing_newline() {
+        let expected = vec![vec![1000, 2000, 3000], vec![4000], vec![5000, 6000]];
+
+        assert_eq!(parse_groups("1000\n2000\n3000\n\n4000\n\n5000\n6000"), expected);
+        assert_eq!(parse_groups("1000\n2000\n3000\n\n4000\n\n5000\n6000\n"), expected);
+        assert_eq!(parse_groups("1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n"), expected);
+    }
+
+    #[test]
+    fn parse_groups_blank_lines() {
+        assert_eq!(parse_groups(""), Vec::<Vec<u64>>::new());
+        assert_eq!(parse_groups("\n\n"), Vec::<Vec<u64>>::new());
+        assert_eq!(parse_groups("\n1\n\n\n2\n"), vec![vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn render_groups_one_elf_per_line() {
+        let groups = parse_groups("1000\n2000\n\n4000\n");
+
+        assert_eq!(render_groups(&groups), "1000 2000\n4000\n");
+        assert_eq!(render_groups(&[]), "");
+    }
+
+    #[test]
+    fn parse_groups_matches_iter_calories() {
+        let input = include_str!("../../puzzles/day01.prod");
+        let totals = parse_groups(input)
+            .iter()
+            .map(|group| group.iter().sum())
+            .collect::<Vec<u64>>();
+
+        assert_eq!(totals, iter_calories(iter_tokens(input)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn iter_tokens_matches_file() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("puzzles/day01.prod");
+        let from_file = iter_calorie_ledger(File::open(path).unwrap()).collect::<Vec<_>>();
+        let from_slice = iter_tokens(include_str!("../../puzzles/day01.prod")).collect::<Vec<_>>();
+
+        assert!(!from_file.is_empty());
+        assert_eq!(from_file, from_slice);
+    }
+
+    #[test]
+    fn iter_tokens_error_tolerance() {
+        let tokens = iter_tokens(" 1 \n\nfoo\n2").collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            [
+                CalorieLedgerToken::Number(1),
+                CalorieLedgerToken::Newline,
+                CalorieLedgerToken::Number(2),
+            ]
+        );
+    }
+
     #[test]
     fn keep_n_largest_stable_replace() {
         let mut values = [0; 3];
@@ -280,6 +509,28 @@ mod tests {
         assert_eq!(values, [5, 2, 7]);
     }
 
+    #[test]
+    fn keep_n_largest_evict_sequence() {
+        let mut values = [0; 3];
+        let evicted = [0, 1, 2, 1, 5, 7, 1, 3]
+            .iter()
+            .map(|&value| keep_n_largest_evict(&mut values, value))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            evicted,
+            [None, Some(0), Some(0), Some(0), Some(1), Some(1), None, Some(2)]
+        );
+        assert_eq!(values, [5, 3, 7]);
+    }
+
+    #[test]
+    fn keep_n_largest_evict_empty() {
+        let mut values: [u64; 0] = [];
+
+        assert_eq!(keep_n_largest_evict(&mut values, 1), None);
+    }
+
     #[test]
     fn challenge_n_largest_generalizes_stage1() {
         let input = [
@@ -304,4 +555,55 @@ mod tests {
         assert_eq!(challenge_n_largest::<2>(input.iter()), 26);
         assert_eq!(challenge_n_largest::<3>(input.iter()), 33);
     }
+
+    // Tests for the histogram of the calorie totals.
+
+    #[test]
+    fn histogram_empty() {
+        assert_eq!(histogram(&[]), None);
+    }
+
+    #[test]
+    fn histogram_single_value() {
+        let expected = Histogram { start: 7, width: 1, counts: vec![3] };
+
+        assert_eq!(histogram(&[7]), Some(Histogram { counts: vec![1], ..expected }));
+        assert_eq!(histogram(&[7, 7, 7]), Some(expected));
+    }
+
+    #[test]
+    fn histogram_sample() {
+        let input = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+        let totals = iter_calories(iter_tokens(input)).collect::<Vec<_>>();
+
+        // 3 buckets of 6667 calories starting at 4000.
+        assert_eq!(
+            histogram(&totals),
+            Some(Histogram { start: 4000, width: 6667, counts: vec![3, 1, 1] })
+        );
+    }
+
+    #[test]
+    fn histogram_counts_every_value() {
+        let totals = (0..100).map(|value| value * value).collect::<Vec<u64>>();
+        let histogram = histogram(&totals).unwrap();
+
+        assert_eq!(histogram.counts.iter().sum::<usize>(), totals.len());
+        assert_eq!(histogram.counts.len(), 10);
+        assert_eq!(histogram.counts[0], 32);
+        assert_eq!(histogram.counts[9], 6);
+    }
+
+    #[cfg(feature = "gen")]
+    #[test]
+    fn generated_input_parses() {
+        use advent_of_code_2022::generate;
+
+        for seed in 0..10 {
+            let input = generate::generate(1, seed, 100).unwrap();
+            let tokens = iter_tokens(&input).collect::<Vec<_>>();
+            assert_eq!(tokens.len(), input.lines().count());
+            assert!(challenge_stage1(tokens.iter()) > 0);
+        }
+    }
 }
@@ -1,11 +1,18 @@
+extern crate advent_of_code_2022;
+extern crate anyhow;
 extern crate clap;
 
-use std::fs::File;
+use std::collections::HashMap;
+use std::fs;
 use std::io::{self, BufRead};
 
+use advent_of_code_2022::input::InputArgs;
+use advent_of_code_2022::log_debug;
+use advent_of_code_2022::runner::{self, RunnerArgs};
+use anyhow::{anyhow, Context, Result};
 use clap::Parser;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 enum GameMove {
     Rock,
     Paper,
@@ -13,6 +20,18 @@ enum GameMove {
 }
 
 impl GameMove {
+    /// All the moves, in the order used to index `OUTCOMES`.
+    const ALL: [GameMove; 3] = [GameMove::Rock, GameMove::Paper, GameMove::Scissors];
+
+    /// The index of the move in `GameMove::ALL`.
+    fn index(&self) -> usize {
+        match *self {
+            GameMove::Rock => 0,
+            GameMove::Paper => 1,
+            GameMove::Scissors => 2,
+        }
+    }
+
     /// The score for the shape you selected:
     ///   - 1 for Rock
     ///   - 2 for Paper
@@ -26,6 +45,7 @@ impl GameMove {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum GameOutcome {
     Loss,
     Draw,
@@ -46,8 +66,21 @@ impl GameOutcome {
     }
 }
 
+/// The outcome of a round for the strategy player, indexed by the opponent's move then the strategy
+/// move (see `GameMove::index`). This is the single source of truth for the rules of the game:
+/// Rock defeats Scissors, Scissors defeats Paper, and Paper defeats Rock.
+const OUTCOMES: [[GameOutcome; 3]; 3] = [
+    // Rock.
+    [GameOutcome::Draw, GameOutcome::Win, GameOutcome::Loss],
+    // Paper.
+    [GameOutcome::Loss, GameOutcome::Draw, GameOutcome::Win],
+    // Scissors.
+    [GameOutcome::Win, GameOutcome::Loss, GameOutcome::Draw],
+];
+
 /// Each game contains many rounds; in each round, the players each simultaneously choose one of
 /// Rock, Paper, or Scissors.
+#[derive(Debug)]
 struct GameRound {
     opponent_move: GameMove,
     strategy_move: GameMove,
@@ -64,17 +97,7 @@ impl GameRound {
     /// Rock defeats Scissors, Scissors defeats Paper, and Paper defeats Rock. If both players
     /// choose the same shape, the round instead ends in a draw.
     fn outcome(&self) -> GameOutcome {
-        match (&self.opponent_move, &self.strategy_move) {
-            (GameMove::Rock, GameMove::Rock) => GameOutcome::Draw,
-            (GameMove::Rock, GameMove::Paper) => GameOutcome::Win,
-            (GameMove::Rock, GameMove::Scissors) => GameOutcome::Loss,
-            (GameMove::Paper, GameMove::Rock) => GameOutcome::Loss,
-            (GameMove::Paper, GameMove::Paper) => GameOutcome::Draw,
-            (GameMove::Paper, GameMove::Scissors) => GameOutcome::Win,
-            (GameMove::Scissors, GameMove::Rock) => GameOutcome::Win,
-            (GameMove::Scissors, GameMove::Paper) => GameOutcome::Loss,
-            (GameMove::Scissors, GameMove::Scissors) => GameOutcome::Draw,
-        }
+        OUTCOMES[self.opponent_move.index()][self.strategy_move.index()]
     }
 }
 
@@ -98,18 +121,43 @@ fn decrypt_strategy_move(encrypted_move: char) -> Option<GameMove> {
     }
 }
 
-fn iter_strategy_guide(strategy_guide: File) -> impl Iterator<Item = (char, char)> {
+/// Splits a line of the guide into the first character of each of its two space-separated
+/// columns, eg. `('A', 'Y')` for `A Y`.
+fn parse_columns(line: &str) -> Result<(char, char)> {
+    let (lhs, rhs) = line
+        .split_once(' ')
+        .ok_or_else(|| anyhow!("expected two columns: {:?}", line))?;
+    match (lhs.chars().next(), rhs.chars().next()) {
+        (Some(lhs), Some(rhs)) => Ok((lhs, rhs)),
+        _ => Err(anyhow!("empty column: {:?}", line)),
+    }
+}
+
+/// Reads the guide line by line, and yields the number of each line, starting at 1, along with
+/// its two columns, or the reason they couldn't be read. Blank lines are skipped.
+fn iter_strategy_guide_lines(
+    strategy_guide: impl io::Read,
+) -> impl Iterator<Item = (usize, Result<(char, char)>)> {
     io::BufReader::new(strategy_guide)
         .lines()
-        .filter_map(|line| {
-            let line = line.ok()?;
-            let line = line.trim();
-            let (lhs, rhs) = line.split_once(' ')?;
-            Some((lhs.chars().nth(0)?, rhs.chars().nth(0)?))
+        .zip(1..)
+        .filter_map(|(line, line_number)| {
+            let columns = match line {
+                Ok(line) if line.trim().is_empty() => return None,
+                Ok(line) => parse_columns(line.trim()),
+                Err(e) => Err(e.into()),
+            };
+            Some((line_number, columns))
         })
 }
 
+/// Same as `iter_strategy_guide_lines`, but silently skips the lines that can't be read.
+fn iter_strategy_guide(strategy_guide: impl io::Read) -> impl Iterator<Item = (char, char)> {
+    iter_strategy_guide_lines(strategy_guide).filter_map(|(_, columns)| columns.ok())
+}
+
 /// A strategically played round: the opponent's move, and the desired game outcome.
+#[derive(Debug)]
 struct GameStrategy {
     opponent_move: GameMove,
     strategy_outcome: GameOutcome,
@@ -119,17 +167,13 @@ impl GameStrategy {
     /// Given the opponent's move, and the desired outcome, returns the round that needs to be
     /// played.
     fn strategy_round(&self) -> GameRound {
-        let strategy_move = match (&self.opponent_move, &self.strategy_outcome) {
-            (GameMove::Rock, GameOutcome::Loss) => GameMove::Scissors,
-            (GameMove::Rock, GameOutcome::Draw) => GameMove::Rock,
-            (GameMove::Rock, GameOutcome::Win) => GameMove::Paper,
-            (GameMove::Paper, GameOutcome::Loss) => GameMove::Rock,
-            (GameMove::Paper, GameOutcome::Draw) => GameMove::Paper,
-            (GameMove::Paper, GameOutcome::Win) => GameMove::Scissors,
-            (GameMove::Scissors, GameOutcome::Loss) => GameMove::Paper,
-            (GameMove::Scissors, GameOutcome::Draw) => GameMove::Scissors,
-            (GameMove::Scissors, GameOutcome::Win) => GameMove::Rock,
-        };
+        // Exactly one move yields each outcome against any given move.
+        let outcomes = &OUTCOMES[self.opponent_move.index()];
+        let strategy_move = GameMove::ALL
+            .iter()
+            .find(|strategy_move| outcomes[strategy_move.index()] == self.strategy_outcome)
+            .cloned()
+            .expect("every outcome is reachable");
         GameRound {
             opponent_move: self.opponent_move.to_owned(),
             strategy_move,
@@ -147,46 +191,163 @@ fn decrypt_strategy_outcome(encrypted_outcome: char) -> Option<GameOutcome> {
     }
 }
 
-#[derive(clap::ValueEnum, Clone)]
-enum ChallengeStage {
-    Stage1,
-    Stage2,
+/// Decrypts a round of the guide as read in stage 1 of the challenge, or reports the offending
+/// character.
+fn round_from_chars(opponent_move: char, strategy_move: char) -> Result<GameRound> {
+    Ok(GameRound {
+        opponent_move: decrypt_opponent_move(opponent_move)
+            .ok_or_else(|| anyhow!("invalid opponent move: {:?}", opponent_move))?,
+        strategy_move: decrypt_strategy_move(strategy_move)
+            .ok_or_else(|| anyhow!("invalid strategy move: {:?}", strategy_move))?,
+    })
+}
+
+/// Decrypts a round of the guide as read in stage 2 of the challenge, or reports the offending
+/// character.
+fn strategy_from_chars(opponent_move: char, strategy_outcome: char) -> Result<GameStrategy> {
+    Ok(GameStrategy {
+        opponent_move: decrypt_opponent_move(opponent_move)
+            .ok_or_else(|| anyhow!("invalid opponent move: {:?}", opponent_move))?,
+        strategy_outcome: decrypt_strategy_outcome(strategy_outcome)
+            .ok_or_else(|| anyhow!("invalid strategy outcome: {:?}", strategy_outcome))?,
+    })
+}
+
+/// Checks that every line of `strategy_guide` is a valid round for both stages of the challenge,
+/// and returns an error naming the first invalid line otherwise. Blank lines are skipped.
+fn check_strategy_guide(strategy_guide: impl io::Read) -> Result<()> {
+    for (line_number, columns) in iter_strategy_guide_lines(strategy_guide) {
+        let context = || format!("line {}", line_number);
+        let (lhs, rhs) = columns.with_context(context)?;
+        round_from_chars(lhs, rhs).with_context(context)?;
+        strategy_from_chars(lhs, rhs).with_context(context)?;
+    }
+    Ok(())
+}
+
+/// Returns the sum of the scores of the rounds of `guide`, as scored by `score_fn`. Rounds that
+/// `score_fn` can't score are skipped.
+fn total_score<F: Fn((char, char)) -> Option<u64>>(
+    guide: impl Iterator<Item = (char, char)>,
+    score_fn: F,
+) -> u64 {
+    guide.filter_map(score_fn).sum()
+}
+
+/// Scores a round of the guide as read in stage 1 of the challenge, or returns `None` if it is
+/// invalid.
+fn score_stage1((opponent_move, strategy_move): (char, char)) -> Option<u64> {
+    round_from_chars(opponent_move, strategy_move)
+        .ok()
+        .map(|round| round.score())
+}
+
+/// Scores a round of the guide as read in stage 2 of the challenge, or returns `None` if it is
+/// invalid.
+fn score_stage2((opponent_move, strategy_outcome): (char, char)) -> Option<u64> {
+    strategy_from_chars(opponent_move, strategy_outcome)
+        .ok()
+        .map(|strategy| strategy.strategy_round().score())
+}
+
+/// Counts how often the opponent plays each move in `guide`. Invalid moves are not counted.
+fn opponent_move_frequencies(guide: &[(char, char)]) -> HashMap<GameMove, usize> {
+    let mut frequencies = HashMap::new();
+    for &(opponent_move, _) in guide {
+        if let Some(opponent_move) = decrypt_opponent_move(opponent_move) {
+            *frequencies.entry(opponent_move).or_insert(0) += 1;
+        }
+    }
+    frequencies
+}
+
+/// Inverse of `decrypt_opponent_move`.
+#[cfg_attr(not(test), allow(dead_code))]
+fn encrypt_opponent_move(game_move: &GameMove) -> char {
+    match *game_move {
+        GameMove::Rock => 'A',
+        GameMove::Paper => 'B',
+        GameMove::Scissors => 'C',
+    }
+}
+
+/// Returns the line of the strategy guide that decodes to `round` under the stage 1 encoding.
+#[cfg_attr(not(test), allow(dead_code))]
+fn encode_stage1(round: &GameRound) -> (char, char) {
+    let strategy_move = match round.strategy_move {
+        GameMove::Rock => 'X',
+        GameMove::Paper => 'Y',
+        GameMove::Scissors => 'Z',
+    };
+    (encrypt_opponent_move(&round.opponent_move), strategy_move)
+}
+
+/// Returns the line of the strategy guide that decodes to `strategy` under the stage 2 encoding.
+#[cfg_attr(not(test), allow(dead_code))]
+fn encode_stage2(strategy: &GameStrategy) -> (char, char) {
+    let strategy_outcome = match strategy.strategy_outcome {
+        GameOutcome::Loss => 'X',
+        GameOutcome::Draw => 'Y',
+        GameOutcome::Win => 'Z',
+    };
+    (encrypt_opponent_move(&strategy.opponent_move), strategy_outcome)
 }
 
 #[derive(Parser)]
 struct CmdlineArgs {
-    // The path to the file to read.
-    strategy_guide_filename: std::path::PathBuf,
+    // The path to the file to read. Defaults to the puzzle input selected by `--sample`/`--prod`.
+    strategy_guide_filename: Option<std::path::PathBuf>,
+
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    runner: RunnerArgs,
 
-    // The part of the challenge to run. Defaults to the first stage.
-    #[clap(short = 'c', long = "challenge", value_enum, default_value_t = ChallengeStage::Stage1)]
-    challenge: ChallengeStage,
+    // Fail on the first invalid round instead of skipping it.
+    #[clap(long = "strict")]
+    strict: bool,
+
+    // Print how often the opponent plays each move instead of solving the puzzle.
+    #[clap(long = "freq")]
+    freq: bool,
 }
 
 fn main() {
     let cmdline_args = CmdlineArgs::parse();
-    let strategy_guide =
-        File::open(cmdline_args.strategy_guide_filename).expect("unable to open input file");
-
-    let iter = iter_strategy_guide(strategy_guide);
-    let total_score = match cmdline_args.challenge {
-        ChallengeStage::Stage1 => iter
-            .filter_map(|(opponent_move, strategy_move)| {
-                let opponent_move = decrypt_opponent_move(opponent_move)?;
-                let strategy_move = decrypt_strategy_move(strategy_move)?;
-                Some(GameRound { opponent_move, strategy_move }.score())
-            })
-            .sum::<u64>(),
-        ChallengeStage::Stage2 => iter
-            .filter_map(|(opponent_move, strategy_outcome)| {
-                let opponent_move = decrypt_opponent_move(opponent_move)?;
-                let strategy_outcome = decrypt_strategy_outcome(strategy_outcome)?;
-                Some(GameStrategy { opponent_move, strategy_outcome }.strategy_round().score())
-            })
-            .sum::<u64>(),
+    let _log = cmdline_args.runner.init_logging();
+    let strategy_guide = match cmdline_args.strategy_guide_filename {
+        Some(path) => fs::read_to_string(path).expect("unable to open input file"),
+        None => cmdline_args.input.load(2).expect("failed to read puzzle input"),
     };
 
-    println!("{total_score}");
+    if cmdline_args.strict {
+        check_strategy_guide(strategy_guide.as_bytes()).expect("invalid strategy guide");
+    }
+    let guide = iter_strategy_guide(strategy_guide.as_bytes()).collect::<Vec<_>>();
+    log_debug!(rounds = guide.len(), "parsed strategy guide");
+    if cmdline_args.runner.print_parsed(|| {
+        guide
+            .iter()
+            .map(|(lhs, rhs)| format!("{} {}\n", lhs, rhs))
+            .collect()
+    }) {
+        return;
+    }
+    if cmdline_args.freq {
+        let frequencies = opponent_move_frequencies(&guide);
+        for opponent_move in GameMove::ALL {
+            let count = frequencies.get(&opponent_move).unwrap_or(&0);
+            println!("{:?}: {}", opponent_move, count);
+        }
+        return;
+    }
+
+    runner::run(
+        &cmdline_args.runner,
+        || total_score(guide.iter().copied(), score_stage1),
+        || total_score(guide.iter().copied(), score_stage2),
+    );
 }
 
 #[cfg(test)]
@@ -276,4 +437,156 @@ mod tests {
             9
         );
     }
+
+    #[test]
+    fn encode_stage1_roundtrip() {
+        for opponent_move in ['A', 'B', 'C'] {
+            for strategy_move in ['X', 'Y', 'Z'] {
+                let round = GameRound {
+                    opponent_move: decrypt_opponent_move(opponent_move).unwrap(),
+                    strategy_move: decrypt_strategy_move(strategy_move).unwrap(),
+                };
+                let (encoded_opponent_move, encoded_strategy_move) = encode_stage1(&round);
+                assert_eq!(decrypt_opponent_move(encoded_opponent_move), Some(round.opponent_move));
+                assert_eq!(decrypt_strategy_move(encoded_strategy_move), Some(round.strategy_move));
+                assert_eq!(
+                    (encoded_opponent_move, encoded_strategy_move),
+                    (opponent_move, strategy_move)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn encode_stage2_roundtrip() {
+        for opponent_move in ['A', 'B', 'C'] {
+            for strategy_outcome in ['X', 'Y', 'Z'] {
+                let strategy = GameStrategy {
+                    opponent_move: decrypt_opponent_move(opponent_move).unwrap(),
+                    strategy_outcome: decrypt_strategy_outcome(strategy_outcome).unwrap(),
+                };
+                let (encoded_opponent_move, encoded_strategy_outcome) = encode_stage2(&strategy);
+                assert_eq!(
+                    decrypt_opponent_move(encoded_opponent_move),
+                    Some(strategy.opponent_move)
+                );
+                assert_eq!(
+                    decrypt_strategy_outcome(encoded_strategy_outcome),
+                    Some(strategy.strategy_outcome)
+                );
+                assert_eq!(
+                    (encoded_opponent_move, encoded_strategy_outcome),
+                    (opponent_move, strategy_outcome)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn strategy_round_reaches_outcome() {
+        for opponent_move in GameMove::ALL {
+            for strategy_outcome in [GameOutcome::Loss, GameOutcome::Draw, GameOutcome::Win] {
+                let round = GameStrategy {
+                    opponent_move: opponent_move.clone(),
+                    strategy_outcome,
+                }
+                .strategy_round();
+                assert_eq!(round.opponent_move, opponent_move);
+                assert_eq!(round.outcome(), strategy_outcome);
+            }
+        }
+    }
+
+    #[test]
+    fn round_from_chars_errors() {
+        assert_eq!(round_from_chars('A', 'Y').unwrap().score(), 8);
+        assert_eq!(
+            round_from_chars('D', 'Y').unwrap_err().to_string(),
+            "invalid opponent move: 'D'"
+        );
+        assert_eq!(
+            round_from_chars('A', 'W').unwrap_err().to_string(),
+            "invalid strategy move: 'W'"
+        );
+    }
+
+    #[test]
+    fn strategy_from_chars_errors() {
+        assert_eq!(strategy_from_chars('A', 'Y').unwrap().strategy_round().score(), 4);
+        assert_eq!(
+            strategy_from_chars('X', 'Y').unwrap_err().to_string(),
+            "invalid opponent move: 'X'"
+        );
+        assert_eq!(
+            strategy_from_chars('C', 'C').unwrap_err().to_string(),
+            "invalid strategy outcome: 'C'"
+        );
+    }
+
+    #[test]
+    fn check_strategy_guide_strict() {
+        let check = |guide: &str| {
+            check_strategy_guide(guide.as_bytes()).map_err(|e| format!("{:#}", e))
+        };
+
+        assert_eq!(check("A Y\nB X\n\nC Z\n"), Ok(()));
+        assert_eq!(check("A Y\n\nB Q\nC Z"), Err("line 3: invalid strategy move: 'Q'".to_owned()));
+        assert_eq!(check("A Y\nAX\nC Z"), Err("line 2: expected two columns: \"AX\"".to_owned()));
+        assert_eq!(check("A Y\nC Z\nA"), Err("line 3: expected two columns: \"A\"".to_owned()));
+        assert_eq!(check("A \nC Z"), Err("line 1: expected two columns: \"A\"".to_owned()));
+        assert_eq!(check("D Y"), Err("line 1: invalid opponent move: 'D'".to_owned()));
+    }
+
+    #[test]
+    fn iter_strategy_guide_skips_malformed_lines() {
+        let guide = iter_strategy_guide("A Y\nAX\n\nB  X\nC Z".as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(guide, [('A', 'Y'), ('B', ' '), ('C', 'Z')]);
+    }
+
+    #[test]
+    fn total_score_synthetic_closure() {
+        let guide = [('A', 'Y'), ('B', 'X'), ('C', 'Z'), ('D', 'W')];
+        let score_fn = |(lhs, rhs): (char, char)| match (lhs, rhs) {
+            ('D', _) => None,
+            (lhs, rhs) => Some(lhs as u64 - 'A' as u64 + rhs as u64 - 'X' as u64),
+        };
+
+        assert_eq!(total_score(guide.iter().copied(), score_fn), 1 + 1 + 4);
+        assert_eq!(total_score(guide.iter().copied(), |_| Some(1)), 4);
+        assert_eq!(total_score(guide.iter().copied(), |_| None), 0);
+        assert_eq!(total_score([].iter().copied(), |_| Some(1)), 0);
+    }
+
+    #[test]
+    fn total_score_sample_guide() {
+        let guide = [('A', 'Y'), ('B', 'X'), ('C', 'Z'), ('A', '?')];
+
+        assert_eq!(total_score(guide.iter().copied(), score_stage1), 15);
+        assert_eq!(total_score(guide.iter().copied(), score_stage2), 12);
+    }
+
+    #[test]
+    fn opponent_move_frequencies_small_guide() {
+        let guide = [('A', 'Y'), ('B', 'X'), ('A', 'Z'), ('D', 'X'), ('A', 'X')];
+        let frequencies = opponent_move_frequencies(&guide);
+
+        assert_eq!(frequencies.len(), 2);
+        assert_eq!(frequencies[&GameMove::Rock], 3);
+        assert_eq!(frequencies[&GameMove::Paper], 1);
+        assert_eq!(frequencies.get(&GameMove::Scissors), None);
+    }
+
+    #[cfg(feature = "gen")]
+    #[test]
+    fn generated_input_parses() {
+        use advent_of_code_2022::generate;
+
+        for seed in 0..10 {
+            let input = generate::generate(2, seed, 100).unwrap();
+            let guide = iter_strategy_guide(input.as_bytes()).collect::<Vec<_>>();
+            assert_eq!(guide.len(), 100);
+            assert!(check_strategy_guide(input.as_bytes()).is_ok());
+        }
+    }
 }
@@ -1,50 +1,223 @@
-extern crate itertools;
+extern crate advent_of_code_2022;
+extern crate anyhow;
+extern crate clap;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 
-use itertools::Itertools;
+use advent_of_code_2022::input::InputArgs;
+use advent_of_code_2022::runner::{self, RunnerArgs};
+use anyhow::{bail, Result};
+use clap::Parser;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
-fn priority(c: char) -> u64 {
-    assert!(('a'..='z').contains(&c) || ('A'..='Z').contains(&c));
+/// Returns the priority of an item type, or `None` if `c` is not an item type (ie. a letter).
+fn priority(c: char) -> Option<u64> {
+    if !c.is_ascii_alphabetic() {
+        return None;
+    }
 
     match c.is_uppercase() {
-        true => c as u64 - 'A' as u64 + 27,
-        false => c as u64 - 'a' as u64 + 1,
+        true => Some(c as u64 - 'A' as u64 + 27),
+        false => Some(c as u64 - 'a' as u64 + 1),
     }
 }
 
-fn main() {
-    let input = include_str!("../../puzzles/day03.prod");
+/// Returns the priority of the item type found in both compartments of the rucksack, if any.
+/// Characters that are not item types are ignored.
+fn rucksack_priority(line: &str) -> Option<u64> {
+    let (lhs, rhs) = line.split_at(line.len() / 2);
 
-    let result: u64 = input
-        .lines()
-        .into_iter()
-        .filter_map(|line| {
-            let (lhs, rhs) = line.split_at(line.len() / 2);
-            let common_char = lhs.chars().find(|c| rhs.contains(*c))?;
+    lhs.chars().filter(|c| rhs.contains(*c)).find_map(priority)
+}
 
-            Some(priority(common_char))
-        })
-        .sum();
-
-    println!("{:?}", result);
-
-    let result: u64 = input
-        .lines()
-        .into_iter()
-        .batching(|iter| {
-            // Note: The following line would be a good candidate for an `ArrayVec`.
-            // https://github.com/tgross35/rfcs/blob/stackvec/text/3316-array-vec.md
-            let lines = iter.take(3).collect::<Vec<_>>();
-            if lines.len() < 3 {
-                None
-            } else {
-                let common_char = lines[0]
-                    .chars()
-                    .find(|c| lines[1].contains(*c) && lines[2].contains(*c))?;
-
-                Some(priority(common_char))
-            }
+/// Returns the priority of the badge of a group, ie. the item type common to its three rucksacks.
+/// Incomplete groups have no badge.
+fn group_priority(lines: &[&str]) -> Option<u64> {
+    if lines.len() < 3 {
+        None
+    } else {
+        lines[0]
+            .chars()
+            .filter(|c| lines[1].contains(*c) && lines[2].contains(*c))
+            .find_map(priority)
+    }
+}
+
+/// Checks that every rucksack only holds item types, and returns an error naming the first
+/// character that is not one otherwise.
+fn check_item_types(input: &str) -> Result<()> {
+    for (index, line) in input.lines().enumerate() {
+        if let Some(c) = line.chars().find(|c| priority(*c).is_none()) {
+            bail!("line {}: invalid item type {:?}", index + 1, c);
+        }
+    }
+    Ok(())
+}
+
+/// Renders the two compartments of each rucksack separated by a space, and the groups of three
+/// rucksacks separated by a blank line.
+fn render_rucksacks(input: &str) -> String {
+    let lines = input.lines().collect::<Vec<_>>();
+    lines
+        .chunks(3)
+        .map(|group| {
+            group
+                .iter()
+                .map(|line| {
+                    let (lhs, rhs) = line.split_at(line.len() / 2);
+                    format!("{} {}\n", lhs, rhs)
+                })
+                .collect::<String>()
         })
-        .sum();
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Sums the priorities of all rucksacks, skipping those with no common item type.
+#[cfg_attr(feature = "rayon", allow(dead_code))]
+fn sum_rucksack_priorities(input: &str) -> u64 {
+    input.lines().filter_map(rucksack_priority).sum()
+}
+
+/// Sums the badge priorities of all groups of three rucksacks, skipping those with no badge.
+#[cfg_attr(feature = "rayon", allow(dead_code))]
+fn sum_group_priorities(input: &str) -> u64 {
+    // Note: `chunks` would be a good candidate for an `ArrayVec` instead of a `Vec`.
+    // https://github.com/tgross35/rfcs/blob/stackvec/text/3316-array-vec.md
+    let lines = input.lines().collect::<Vec<_>>();
+
+    lines.chunks(3).filter_map(group_priority).sum()
+}
+
+/// Parallel version of `sum_rucksack_priorities`.
+#[cfg(feature = "rayon")]
+fn par_sum_rucksack_priorities(input: &str) -> u64 {
+    input.par_lines().filter_map(rucksack_priority).sum()
+}
+
+/// Parallel version of `sum_group_priorities`.
+#[cfg(feature = "rayon")]
+fn par_sum_group_priorities(input: &str) -> u64 {
+    let lines = input.lines().collect::<Vec<_>>();
+
+    lines.par_chunks(3).filter_map(group_priority).sum()
+}
+
+#[derive(Parser)]
+struct CmdlineArgs {
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    runner: RunnerArgs,
+
+    // Reject the input if a rucksack holds characters that are not item types, instead of ignoring
+    // them.
+    #[clap(long = "strict")]
+    strict: bool,
+}
+
+fn main() {
+    let cmdline_args = CmdlineArgs::parse();
+    let _log = cmdline_args.runner.init_logging();
+    let input = &cmdline_args
+        .input
+        .load(3)
+        .expect("failed to read puzzle input");
+    if cmdline_args.runner.print_parsed(|| render_rucksacks(input)) {
+        return;
+    }
+    if cmdline_args.strict {
+        check_item_types(input).expect("invalid rucksack");
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    let (part1, part2) = (sum_rucksack_priorities, sum_group_priorities);
+    #[cfg(feature = "rayon")]
+    let (part1, part2) = (par_sum_rucksack_priorities, par_sum_group_priorities);
+
+    runner::run(&cmdline_args.runner, || part1(input), || part2(input));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("../../puzzles/day03.test");
 
-    println!("{:?}", result);
+    #[test]
+    fn render_sample_rucksacks() {
+        let rendered = render_rucksacks(SAMPLE);
+        let lines = rendered.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0], "vJrwpWtwJgWr hcsFMMfFFhFp");
+        assert_eq!(lines[3], "");
+    }
+
+    #[test]
+    fn sum_priorities_sample() {
+        assert_eq!(sum_rucksack_priorities(SAMPLE), 157);
+        assert_eq!(sum_group_priorities(SAMPLE), 70);
+    }
+
+    #[test]
+    fn sum_priorities_skip_missing_common_item() {
+        let input = "abcd\nvJrwpWtwJgWrhcsFMMfFFhFp\nefgh";
+
+        assert_eq!(sum_rucksack_priorities(input), 16);
+        assert_eq!(sum_group_priorities(input), 0);
+    }
+
+    #[test]
+    fn priority_of_non_letters() {
+        assert_eq!(priority('a'), Some(1));
+        assert_eq!(priority('Z'), Some(52));
+        assert_eq!(priority('1'), None);
+        assert_eq!(priority(' '), None);
+        assert_eq!(priority('é'), None);
+    }
+
+    #[test]
+    fn sum_priorities_ignore_digits() {
+        // The first rucksack has both `1` and `a` in common, the second only `1`.
+        let input = "ab1c1a\nab1c1d\nz1yx1z";
+
+        assert_eq!(sum_rucksack_priorities(input), 1 + 26);
+        assert_eq!(sum_group_priorities(input), 0);
+        assert_eq!(sum_group_priorities("1a\n1ab\n1ac"), 1);
+    }
+
+    #[test]
+    fn check_item_types_strict() {
+        assert!(check_item_types(SAMPLE).is_ok());
+        assert_eq!(
+            check_item_types("abcd\nab1c1a").unwrap_err().to_string(),
+            "line 2: invalid item type '1'"
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_matches_sequential() {
+        let input = [SAMPLE, "abcd\nefgh\n", SAMPLE].concat();
+
+        assert_eq!(par_sum_rucksack_priorities(&input), sum_rucksack_priorities(&input));
+        assert_eq!(par_sum_group_priorities(&input), sum_group_priorities(&input));
+    }
+
+    #[cfg(feature = "gen")]
+    #[test]
+    fn generated_input_parses() {
+        use advent_of_code_2022::generate;
+
+        for seed in 0..10 {
+            let input = generate::generate(3, seed, 100).unwrap();
+            let lines = input.lines().collect::<Vec<_>>();
+            assert_eq!(lines.len(), 300);
+            assert!(lines.iter().all(|line| rucksack_priority(line).is_some()));
+            assert!(lines.chunks(3).all(|group| group_priority(group).is_some()));
+        }
+    }
 }
@@ -1,6 +1,11 @@
+extern crate advent_of_code_2022;
 extern crate anyhow;
+extern crate clap;
 
+use advent_of_code_2022::input::{count_lines_matching, InputArgs};
+use advent_of_code_2022::runner::{self, RunnerArgs};
 use anyhow::{anyhow, Result};
+use clap::Parser;
 use std::fmt;
 use std::ops::RangeInclusive;
 use std::str::FromStr;
@@ -10,6 +15,17 @@ struct RangePair<T: PartialOrd + FromStr> {
     second: RangeInclusive<T>,
 }
 
+/// Any number of ranges, as found on a single line of the input.
+struct RangeGroup<T: PartialOrd + FromStr> {
+    ranges: Vec<RangeInclusive<T>>,
+}
+
+/// The ranges of a single line of the input: a pair, checked directly, or a group of more ranges.
+enum Assignments<T: PartialOrd + FromStr> {
+    Pair(RangePair<T>),
+    Group(RangeGroup<T>),
+}
+
 trait RangeInclusiveExtension {
     /// Whether `self` fully contains `other`.
     ///
@@ -19,15 +35,121 @@ trait RangeInclusiveExtension {
     /// assert_ne!((1..=5).fully_contains(2..=7));
     /// ```
     fn fully_contains(&self, other: &Self) -> bool;
+
+    /// The range shared by `self` and `other`, if any.
+    ///
+    /// ```
+    /// assert_eq!((1..=5).intersection(&(3..=7)), Some(3..=5));
+    /// assert_eq!((1..=5).intersection(&(6..=7)), None);
+    /// ```
+    fn intersection(&self, other: &Self) -> Option<Self>
+    where
+        Self: Sized;
+
+    /// Whether `self` and `other` share no value.
+    ///
+    /// ```
+    /// assert!((1..=3).disjoint(&(4..=5)));
+    /// assert!(!(1..=3).disjoint(&(3..=5)));
+    /// ```
+    fn disjoint(&self, other: &Self) -> bool;
+
+    /// Whether `self` and `other` share at least one value.
+    fn overlaps(&self, other: &Self) -> bool {
+        !self.disjoint(other)
+    }
+
+    /// Whether `self` ends where `other` starts, or the other way around, ie. they share exactly
+    /// one boundary.
+    ///
+    /// ```
+    /// assert!((1..=3).touches(&(3..=5)));
+    /// assert!(!(1..=3).touches(&(4..=5)));
+    /// ```
+    fn touches(&self, other: &Self) -> bool;
 }
 
-impl<T: PartialOrd> RangeInclusiveExtension for RangeInclusive<T> {
+/// Returns the range from the greater start to the lesser end of `lhs` and `rhs`. Its start is past
+/// its end if they don't overlap.
+fn clamped_range<T: PartialOrd + Clone>(
+    lhs: &RangeInclusive<T>,
+    rhs: &RangeInclusive<T>,
+) -> RangeInclusive<T> {
+    let start = if lhs.start() >= rhs.start() {
+        lhs.start()
+    } else {
+        rhs.start()
+    };
+    let end = if lhs.end() <= rhs.end() {
+        lhs.end()
+    } else {
+        rhs.end()
+    };
+
+    start.clone()..=end.clone()
+}
+
+impl<T: PartialOrd + Clone> RangeInclusiveExtension for RangeInclusive<T> {
     fn fully_contains(&self, other: &Self) -> bool {
         self.start() <= other.start() && other.end() <= self.end()
     }
+
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        let range = clamped_range(self, other);
+
+        (range.start() <= range.end()).then_some(range)
+    }
+
+    fn disjoint(&self, other: &Self) -> bool {
+        self.end() < other.start() || other.end() < self.start()
+    }
+
+    fn touches(&self, other: &Self) -> bool {
+        self.end() == other.start() || other.end() == self.start()
+    }
+}
+
+impl<T: PartialOrd + FromStr + Clone> RangeGroup<T> {
+    /// Iterates over every pair of distinct ranges of the group, in both orders.
+    fn pairs(&self) -> impl Iterator<Item = (&RangeInclusive<T>, &RangeInclusive<T>)> {
+        self.ranges.iter().enumerate().flat_map(move |(i, lhs)| {
+            self.ranges
+                .iter()
+                .enumerate()
+                .filter(move |(j, _)| i != *j)
+                .map(move |(_, rhs)| (lhs, rhs))
+        })
+    }
+
+    /// Whether any range of the group fully contains another one.
+    fn any_fully_contains_any(&self) -> bool {
+        self.pairs().any(|(lhs, rhs)| lhs.fully_contains(rhs))
+    }
+
+    /// Whether every range of the group overlaps every other one.
+    fn all_pairwise_overlap(&self) -> bool {
+        self.pairs().all(|(lhs, rhs)| lhs.overlaps(rhs))
+    }
+
+    /// The range shared by all ranges of the group, if any.
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn common_intersection(&self) -> Option<RangeInclusive<T>> {
+        let (first, rest) = self.ranges.split_first()?;
+        rest.iter()
+            .try_fold(first.clone(), |acc, range| acc.intersection(range))
+    }
 }
 
-impl<T: PartialOrd + FromStr> RangePair<T> {
+impl<T: PartialOrd + FromStr + Clone> RangePair<T> {
+    /// Copies both ranges into a `RangeGroup`. This allocates, so the pair checks below compare
+    /// the two ranges directly instead.
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn group(&self) -> RangeGroup<T> {
+        RangeGroup {
+            ranges: vec![self.first.clone(), self.second.clone()],
+        }
+    }
+
     /// Whether `self.first` fully contains `self.second`, or vice-versa.
     fn any_fully_contains_other(&self) -> bool {
         self.first.fully_contains(&self.second) || self.second.fully_contains(&self.first)
@@ -37,8 +159,107 @@ impl<T: PartialOrd + FromStr> RangePair<T> {
     ///   - one fully contains the other, or
     ///   - they share a common sub-range
     fn overlaps(&self) -> bool {
-        self.any_fully_contains_other()
-            || (self.first.end() >= self.second.start() && self.first.start() <= self.second.end())
+        self.first.overlaps(&self.second)
+    }
+
+    /// Restricts both ranges to `bounds`.
+    ///
+    /// A range outside of `bounds` becomes empty: its start is then past its end, as with any empty
+    /// `RangeInclusive`, so that `is_empty` holds and `section_count` is 0.
+    ///
+    /// ```
+    /// let pair = "2-4,6-8".parse::<RangePair<u64>>().unwrap();
+    /// assert_eq!(pair.clamp_to(&(3..=5)).to_string(), "3-4,6-5");
+    /// ```
+    fn clamp_to(&self, bounds: &RangeInclusive<T>) -> RangePair<T> {
+        RangePair {
+            first: clamped_range(&self.first, bounds),
+            second: clamped_range(&self.second, bounds),
+        }
+    }
+}
+
+/// Returns the number of sections in `range`.
+fn section_count(range: &RangeInclusive<u64>) -> u64 {
+    if range.is_empty() {
+        0
+    } else {
+        range.end() - range.start() + 1
+    }
+}
+
+/// Returns the sum of the sections covered by each pair of `input`, skipping the lines that don't
+/// parse. Sections covered by both ranges of a pair are counted once, but sections covered by
+/// several pairs are counted once per pair: overlaps across lines are not deduplicated.
+fn total_covered(input: &str) -> u64 {
+    input
+        .lines()
+        .filter_map(|line| line.parse::<RangePair<u64>>().ok())
+        .map(|pair| pair.length_covered())
+        .sum()
+}
+
+impl<T: PartialOrd + FromStr + fmt::Display> fmt::Display for RangePair<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{},{}-{}",
+            self.first.start(),
+            self.first.end(),
+            self.second.start(),
+            self.second.end()
+        )
+    }
+}
+
+impl RangePair<u64> {
+    /// Returns the number of distinct sections covered by either range. Sections covered by both
+    /// ranges are only counted once.
+    fn length_covered(&self) -> u64 {
+        let overlap = self
+            .first
+            .intersection(&self.second)
+            .map_or(0, |range| section_count(&range));
+
+        section_count(&self.first) + section_count(&self.second) - overlap
+    }
+
+    /// Renders both ranges as bars on a shared number line of `width` cells spanning from the
+    /// smallest start to the largest end of the pair. Cells covered by a single range are drawn
+    /// with `#`, cells covered by both with `X`, and uncovered cells with `.`.
+    ///
+    /// ```text
+    /// 2-4: #XX...
+    /// 3-7: .XX###
+    /// ```
+    fn render(&self, width: usize) -> String {
+        let min = *self.first.start().min(self.second.start());
+        let max = *self.first.end().max(self.second.end());
+        let span = max.saturating_sub(min) + 1;
+        // The section shown by each cell of the number line.
+        let sections = (0..width as u64)
+            .map(|cell| min + cell * span / width as u64)
+            .collect::<Vec<_>>();
+
+        let labels = [&self.first, &self.second].map(|r| format!("{}-{}", r.start(), r.end()));
+        let label_width = labels.iter().map(String::len).max().unwrap_or_default();
+
+        [(&self.first, &self.second), (&self.second, &self.first)]
+            .iter()
+            .zip(labels.iter())
+            .map(|((range, other), label)| {
+                let bar = sections
+                    .iter()
+                    .map(|section| match (range.contains(section), other.contains(section)) {
+                        (true, true) => 'X',
+                        (true, false) => '#',
+                        (false, _) => '.',
+                    })
+                    .collect::<String>();
+                format!("{label:>label_width$}: {bar}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 }
 
@@ -78,24 +299,379 @@ where
     }
 }
 
-/// Parses `input` and converts each line into a pair of inclusive ranges.
-/// Returns the number of lines that matches `predicate`.
-fn count_by<T, Predicate>(input: &str, predicate: Predicate) -> usize
+impl<T> FromStr for RangeGroup<T>
 where
     T: PartialOrd + FromStr,
     <T as FromStr>::Err: fmt::Debug,
-    Predicate: Fn(&RangePair<T>) -> bool,
 {
-    input
-        .lines()
-        .into_iter()
-        .filter_map(|line| predicate(&line.parse().ok()?).then_some(()))
-        .count()
+    type Err = anyhow::Error;
+
+    /// Parses a comma-separated list of at least two ranges of the form `"A-B,C-D,E-F"`.
+    fn from_str(s: &str) -> Result<Self> {
+        let ranges = s
+            .split(',')
+            .map(parse_inclusive_range)
+            .collect::<Result<Vec<_>>>()?;
+        if ranges.len() < 2 {
+            return Err(anyhow!("expected at least two ranges: {:?}", s));
+        }
+
+        Ok(RangeGroup { ranges })
+    }
+}
+
+impl<T: PartialOrd + FromStr + Clone> Assignments<T> {
+    /// Whether any range of the line fully contains another one.
+    fn any_fully_contains_other(&self) -> bool {
+        match self {
+            Assignments::Pair(pair) => pair.any_fully_contains_other(),
+            Assignments::Group(group) => group.any_fully_contains_any(),
+        }
+    }
+
+    /// Whether every range of the line overlaps every other one.
+    fn overlaps(&self) -> bool {
+        match self {
+            Assignments::Pair(pair) => pair.overlaps(),
+            Assignments::Group(group) => group.all_pairwise_overlap(),
+        }
+    }
+}
+
+impl<T> FromStr for Assignments<T>
+where
+    T: PartialOrd + FromStr,
+    <T as FromStr>::Err: fmt::Debug,
+{
+    type Err = anyhow::Error;
+
+    /// Parses a line as a `RangeGroup` if it holds three ranges or more, or as a `RangePair`
+    /// otherwise.
+    fn from_str(s: &str) -> Result<Self> {
+        if s.matches(',').count() >= 2 {
+            Ok(Assignments::Group(s.parse()?))
+        } else {
+            Ok(Assignments::Pair(s.parse()?))
+        }
+    }
+}
+
+/// Parses `input` and converts each line into an `R`, eg. a `RangePair` or `Assignments`.
+/// Returns the number of lines that matches `predicate`, skipping the lines that don't parse.
+fn count_by<R, Predicate>(input: &str, predicate: Predicate) -> usize
+where
+    R: FromStr,
+    Predicate: Fn(&R) -> bool,
+{
+    count_lines_matching(input, predicate)
+}
+
+/// Counts the pairs where one range fully contains the other, and the pairs that overlap, parsing
+/// the sections as `T`.
+fn solve<T>(args: &RunnerArgs, input: &str)
+where
+    T: PartialOrd + FromStr + Clone,
+    <T as FromStr>::Err: fmt::Debug,
+{
+    runner::run(
+        args,
+        || count_by(input, Assignments::<T>::any_fully_contains_other),
+        || count_by(input, Assignments::<T>::overlaps),
+    );
+}
+
+/// The integer type the sections are parsed as.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum IntWidth {
+    #[value(name = "32")]
+    U32,
+    #[value(name = "64")]
+    U64,
+    #[value(name = "128")]
+    U128,
+}
+
+#[derive(Parser)]
+struct CmdlineArgs {
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    runner: RunnerArgs,
+
+    // Print the sum of the sections covered by each pair instead of solving the puzzle. Sections
+    // covered by several pairs are counted once per pair.
+    #[clap(long = "total-covered", alias = "covered")]
+    total_covered: bool,
+
+    // Print the number of pairs whose ranges share exactly one boundary instead of solving the
+    // puzzle.
+    #[clap(long = "touching")]
+    touching: bool,
+
+    // The width in bits of the unsigned integers the sections are parsed as when solving the
+    // puzzle. Lines with sections that don't fit are skipped.
+    #[clap(long = "int-width", value_enum, default_value_t = IntWidth::U64)]
+    int_width: IntWidth,
+
+    // Render each pair on a number line of the given width instead of solving the puzzle.
+    #[clap(long = "render", value_name = "WIDTH")]
+    render: Option<usize>,
+
+    // Print each pair restricted to the given range of sections, eg. `10-20`, instead of solving
+    // the puzzle. Ranges outside of it are printed with their start past their end.
+    #[clap(long = "clamp", value_name = "RANGE", value_parser = parse_inclusive_range::<u64>)]
+    clamp: Option<RangeInclusive<u64>>,
 }
 
 fn main() {
-    let input = include_str!("../../puzzles/day04.prod");
+    let cmdline_args = CmdlineArgs::parse();
+    let _log = cmdline_args.runner.init_logging();
+    let input = &cmdline_args
+        .input
+        .load(4)
+        .expect("failed to read puzzle input");
+
+    if cmdline_args.runner.print_parsed(|| {
+        input
+            .lines()
+            .filter_map(|line| line.parse::<RangePair<u64>>().ok())
+            .map(|pair| format!("{}\n", pair))
+            .collect()
+    }) {
+        return;
+    }
+
+    if cmdline_args.total_covered {
+        println!("{}", total_covered(input));
+        return;
+    }
+
+    if cmdline_args.touching {
+        let count = input
+            .lines()
+            .filter_map(|line| line.parse::<RangePair<u64>>().ok())
+            .filter(|pair| pair.first.touches(&pair.second))
+            .count();
+        println!("{}", count);
+        return;
+    }
+
+    if let Some(width) = cmdline_args.render {
+        for pair in input
+            .lines()
+            .filter_map(|line| line.parse::<RangePair<u64>>().ok())
+        {
+            println!("{}\n{}\n", pair, pair.render(width));
+        }
+        return;
+    }
+
+    if let Some(bounds) = &cmdline_args.clamp {
+        for pair in input
+            .lines()
+            .filter_map(|line| line.parse::<RangePair<u64>>().ok())
+        {
+            let clamped = pair.clamp_to(bounds);
+            println!("{} -> {} ({} sections)", pair, clamped, clamped.length_covered());
+        }
+        return;
+    }
+
+    match cmdline_args.int_width {
+        IntWidth::U32 => solve::<u32>(&cmdline_args.runner, input),
+        IntWidth::U64 => solve::<u64>(&cmdline_args.runner, input),
+        IntWidth::U128 => solve::<u128>(&cmdline_args.runner, input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disjoint_and_touching_ranges() {
+        assert!((1..=3).touches(&(3..=5)));
+        assert!((3..=5).touches(&(1..=3)));
+        assert!((1..=3).overlaps(&(3..=5)));
+        assert!(!(1..=3).disjoint(&(3..=5)));
+
+        assert!(!(1..=3).touches(&(4..=5)));
+        assert!(!(1..=3).overlaps(&(4..=5)));
+        assert!((1..=3).disjoint(&(4..=5)));
+        assert!((4..=5).disjoint(&(1..=3)));
+
+        assert!(!(1..=5).touches(&(2..=4)));
+        assert!((1..=5).overlaps(&(2..=4)));
+    }
+
+    #[test]
+    fn intersection_of_ranges() {
+        assert_eq!((2..=8).intersection(&(3..=7)), Some(3..=7));
+        assert_eq!((5..=7).intersection(&(7..=9)), Some(7..=7));
+        assert_eq!((2..=4).intersection(&(6..=8)), None);
+    }
+
+    #[test]
+    fn length_covered_fully_contained() {
+        let pair: RangePair<u64> = "2-8,3-7".parse().unwrap();
+        assert_eq!(pair.length_covered(), 7);
+    }
+
+    #[test]
+    fn length_covered_disjoint() {
+        let pair: RangePair<u64> = "2-4,6-8".parse().unwrap();
+        assert_eq!(pair.length_covered(), 6);
+    }
+
+    #[test]
+    fn length_covered_overlapping() {
+        let pair: RangePair<u64> = "5-7,7-9".parse().unwrap();
+        assert_eq!(pair.length_covered(), 5);
+    }
+
+    #[test]
+    fn total_covered_two_lines() {
+        // 3 + 3 sections for the first pair, 5 + 5 - 3 for the second one. Sections 2 to 4 and 6
+        // are covered by both lines, but counted once per line.
+        assert_eq!(total_covered("2-4,6-8\n2-6,4-8\n"), 13);
+        assert_eq!(total_covered("2-4,6-8\nnot a pair\n"), 6);
+    }
+
+    #[test]
+    fn display_range_pair() {
+        let pair: RangePair<u64> = "2-4,6-8".parse().unwrap();
+        assert_eq!(pair.to_string(), "2-4,6-8");
+    }
+
+    #[test]
+    fn render_overlapping_pair() {
+        let pair: RangePair<u64> = "2-4,3-7".parse().unwrap();
+        assert_eq!(pair.render(6), "2-4: #XX...\n3-7: .XX###");
+    }
+
+    #[test]
+    fn render_scaled_pair() {
+        let pair: RangePair<u64> = "1-10,91-100".parse().unwrap();
+        assert_eq!(pair.render(10), "  1-10: #.........\n91-100: .........#");
+    }
+
+    #[test]
+    fn range_group_three_ranges() {
+        let group: RangeGroup<u64> = "2-8,3-7,6-9".parse().unwrap();
+
+        assert!(group.any_fully_contains_any());
+        assert!(group.all_pairwise_overlap());
+        assert_eq!(group.common_intersection(), Some(6..=7));
+    }
+
+    #[test]
+    fn range_group_partial_overlaps() {
+        let group: RangeGroup<u64> = "1-3,3-5,5-7".parse().unwrap();
+
+        assert!(!group.any_fully_contains_any());
+        assert!(!group.all_pairwise_overlap());
+        assert_eq!(group.common_intersection(), None);
+    }
+
+    #[test]
+    fn pair_checks_match_group() {
+        for line in [
+            "2-4,6-8", "2-3,4-5", "5-7,7-9", "2-8,3-7", "6-6,4-6", "2-6,4-8",
+        ] {
+            let pair = line.parse::<RangePair<u64>>().unwrap();
+            let group = pair.group();
+
+            assert_eq!(pair.any_fully_contains_other(), group.any_fully_contains_any(), "{}", line);
+            assert_eq!(pair.overlaps(), group.all_pairwise_overlap(), "{}", line);
+        }
+    }
 
-    println!("{:?}", count_by(input, RangePair::<u64>::any_fully_contains_other));
-    println!("{:?}", count_by(input, RangePair::<u64>::overlaps));
+    #[test]
+    fn count_lines_with_more_ranges() {
+        let input = "2-4,6-8\n2-8,3-7,6-9\n1-3,3-5,5-7\n5-7,7-9\n1-3\n";
+
+        assert_eq!(count_by(input, Assignments::<u64>::any_fully_contains_other), 1);
+        assert_eq!(count_by(input, Assignments::<u64>::overlaps), 2);
+    }
+
+    #[test]
+    fn range_group_degenerate() {
+        assert!("1-3".parse::<RangeGroup<u64>>().is_err());
+        assert!("1-3,x".parse::<RangeGroup<u64>>().is_err());
+    }
+
+    #[cfg(feature = "gen")]
+    #[test]
+    fn generated_input_parses() {
+        use advent_of_code_2022::generate;
+
+        for seed in 0..10 {
+            let input = generate::generate(4, seed, 100).unwrap();
+            for line in input.lines() {
+                assert!(line.parse::<RangePair<u64>>().is_ok(), "{:?}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn clamp_to_bounds() {
+        let pair = "2-4,6-8".parse::<RangePair<u64>>().unwrap();
+
+        let clamped = pair.clamp_to(&(3..=7));
+        assert_eq!((clamped.first, clamped.second), (3..=4, 6..=7));
+        let clamped = pair.clamp_to(&(0..=100));
+        assert_eq!((clamped.first, clamped.second), (2..=4, 6..=8));
+        let clamped = pair.clamp_to(&(4..=6));
+        assert_eq!((clamped.first, clamped.second), (4..=4, 6..=6));
+    }
+
+    #[test]
+    fn clamp_to_range_outside_bounds() {
+        let pair = "2-4,6-8".parse::<RangePair<u64>>().unwrap();
+
+        let clamped = pair.clamp_to(&(5..=7));
+        assert!(clamped.first.is_empty());
+        assert_eq!(section_count(&clamped.first), 0);
+        assert_eq!(clamped.second, 6..=7);
+        assert_eq!(clamped.length_covered(), 2);
+        assert_eq!(clamped.to_string(), "5-4,6-7");
+
+        // Both ranges outside of the bounds, on either side.
+        let clamped = pair.clamp_to(&(5..=5));
+        assert!(clamped.first.is_empty() && clamped.second.is_empty());
+        assert_eq!(clamped.length_covered(), 0);
+        assert!(!clamped.overlaps());
+    }
+
+    #[test]
+    fn count_by_int_widths() {
+        let input = include_str!("../../puzzles/day04.test");
+
+        assert_eq!(count_by(input, RangePair::<u32>::any_fully_contains_other), 2);
+        assert_eq!(count_by(input, RangePair::<u128>::any_fully_contains_other), 2);
+        assert_eq!(count_by(input, RangePair::<u32>::overlaps), 4);
+        assert_eq!(count_by(input, RangePair::<u128>::overlaps), 4);
+
+        let input = include_str!("../../puzzles/day04.prod");
+        let contains = count_by(input, RangePair::<u64>::any_fully_contains_other);
+        let overlaps = count_by(input, RangePair::<u64>::overlaps);
+        assert_eq!(count_by(input, RangePair::<u32>::any_fully_contains_other), contains);
+        assert_eq!(count_by(input, RangePair::<u128>::any_fully_contains_other), contains);
+        assert_eq!(count_by(input, RangePair::<u32>::overlaps), overlaps);
+        assert_eq!(count_by(input, RangePair::<u128>::overlaps), overlaps);
+    }
+
+    #[test]
+    fn count_by_large_sections() {
+        // Beyond `u64::MAX`, so these lines are skipped unless parsed as `u128`.
+        let input =
+            "18446744073709551616-18446744073709551620,18446744073709551617-18446744073709551618\n\
+             2-4,6-8\n";
+
+        assert_eq!(count_by(input, RangePair::<u64>::any_fully_contains_other), 0);
+        assert_eq!(count_by(input, RangePair::<u128>::any_fully_contains_other), 1);
+        assert_eq!(count_by(input, RangePair::<u128>::overlaps), 1);
+        assert_eq!(count_by(input, RangePair::<u32>::overlaps), 0);
+    }
 }
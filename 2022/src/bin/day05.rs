@@ -1,17 +1,65 @@
-use std::iter::repeat;
+use std::fmt;
 use std::str::FromStr;
 
-use anyhow::{anyhow, Ok, Result};
+use advent_of_code_2022::input::InputArgs;
+use advent_of_code_2022::runner::{self, RunnerArgs};
+use advent_of_code_2022::{log, log_debug};
+use anyhow::{anyhow, Context, Ok, Result};
+use clap::Parser;
 
+extern crate advent_of_code_2022;
 extern crate anyhow;
+extern crate clap;
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 struct CrateStacks {
     stacks: Vec<Vec<char>>,
 }
 
+/// How many crates a move command moves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CrateCount {
+    Count(usize),
+    /// Every crate of the source stack, however many there are when the move is played.
+    All,
+}
+
+impl CrateCount {
+    /// Returns the number of crates to move off a source stack of `src_height` crates.
+    fn resolve(self, src_height: usize) -> usize {
+        match self {
+            CrateCount::Count(count) => count,
+            CrateCount::All => src_height,
+        }
+    }
+}
+
+impl fmt::Display for CrateCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CrateCount::Count(count) => write!(f, "{}", count),
+            CrateCount::All => write!(f, "all"),
+        }
+    }
+}
+
+impl FromStr for CrateCount {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "all" => Ok(CrateCount::All),
+            _ => Ok(CrateCount::Count(
+                s.parse()
+                    .map_err(|e| anyhow!("failed to parse crate_number: {:?}", e))?,
+            )),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
 struct MoveCommand {
-    crate_count: usize,
+    crate_count: CrateCount,
     src_index: usize,
     dst_index: usize,
 }
@@ -19,14 +67,13 @@ struct MoveCommand {
 impl FromStr for MoveCommand {
     type Err = anyhow::Error;
 
-    /// Parses a move command of the form `move COUNT from SRC to DST`.
+    /// Parses a move command of the form `move COUNT from SRC to DST`, where `COUNT` may be `all`.
     fn from_str(s: &str) -> Result<Self> {
         let mut iter = s.splitn(6, ' ').skip(1).step_by(2);
         let crate_count = iter
             .next()
             .ok_or_else(|| anyhow!("unpexpected move syntax"))?
-            .parse()
-            .map_err(|e| anyhow!("failed to parse crate_number: {:?}", e))?;
+            .parse()?;
         let src_index = iter
             .next()
             .ok_or_else(|| anyhow!("unpexpected move syntax"))?
@@ -46,80 +93,618 @@ impl FromStr for MoveCommand {
     }
 }
 
+/// Formats a move command as `move COUNT from SRC to DST`, as parsed by `from_str`.
+impl fmt::Display for MoveCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.crate_count, self.src_index, self.dst_index)
+    }
+}
+
+/// The model of the crane playing the moves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CrateMover {
+    /// Moves one crate at a time, reversing the order of the moved crates.
+    Model9000,
+    /// Moves all crates at once, preserving their order.
+    Model9001,
+}
+
+impl CrateMover {
+    /// Describes what happens to the order of the moved crates.
+    fn order(self) -> &'static str {
+        match self {
+            CrateMover::Model9000 => "reversed",
+            CrateMover::Model9001 => "preserved",
+        }
+    }
+}
+
+impl fmt::Display for CrateMover {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CrateMover::Model9000 => write!(f, "9000"),
+            CrateMover::Model9001 => write!(f, "9001"),
+        }
+    }
+}
+
 impl CrateStacks {
+    /// Returns the number of crates `move_cmd` moves off its source stack.
+    fn crate_count(&self, move_cmd: &MoveCommand) -> usize {
+        move_cmd
+            .crate_count
+            .resolve(self.stacks[move_cmd.src_index - 1].len())
+    }
+
+    /// Plays `move_cmd` with `mover`, and returns the moved crates as they end up on top of the
+    /// destination stack, from bottom to top.
+    /// Panics if the source stack holds fewer crates than moved.
+    fn play_move(&mut self, move_cmd: &MoveCommand, mover: CrateMover) -> &[char] {
+        let crate_count = self.crate_count(move_cmd);
+        let src = &mut self.stacks[move_cmd.src_index - 1];
+        let src_size = src.len();
+        let mut moved = src.split_off(
+            src_size
+                .checked_sub(crate_count)
+                .expect("unexpected empty stack"),
+        );
+        if mover == CrateMover::Model9000 {
+            moved.reverse();
+        }
+
+        let dst = &mut self.stacks[move_cmd.dst_index - 1];
+        dst.extend(moved);
+        &dst[dst.len() - crate_count..]
+    }
+
     fn play_move_with_cratemover_9000(&mut self, move_cmd: &MoveCommand) {
-        repeat(()).take(move_cmd.crate_count).for_each(|()| {
-            let top = self.stacks[move_cmd.src_index - 1]
-                .pop()
-                .expect("unexpected empty stack");
-            self.stacks[move_cmd.dst_index - 1].push(top);
-        })
+        self.play_move(move_cmd, CrateMover::Model9000);
     }
 
     fn play_move_with_cratemover_9001(&mut self, move_cmd: &MoveCommand) {
-        let src_size = self.stacks[move_cmd.src_index - 1].len();
-        let tail = self.stacks[move_cmd.src_index - 1].split_off(src_size - move_cmd.crate_count);
-        self.stacks[move_cmd.dst_index - 1].extend(tail);
+        self.play_move(move_cmd, CrateMover::Model9001);
     }
 
-    /// Returns a `String` made out the top characters of each stack.
-    /// Panics if one of the stack is empty.
+    /// Returns a `String` made out the top characters of each stack. Empty stacks, such as the
+    /// source of a `move all` command, are skipped.
     fn get_top_crates(&self) -> String {
         self.stacks
             .iter()
-            .map(|stack| stack.last().expect("unexpected empty stack"))
+            .filter_map(|stack| stack.last())
             .collect::<String>()
     }
+
+    /// Returns the number of crates on each stack.
+    fn stack_heights(&self) -> Vec<usize> {
+        self.stacks.iter().map(Vec::len).collect()
+    }
+
+    /// Returns the number of crates across all stacks.
+    fn total_crates(&self) -> usize {
+        self.stacks.iter().map(Vec::len).sum()
+    }
+
+    /// Returns the labels of the crates across all stacks, sorted, so that two states holding the
+    /// same crates compare equal whatever their positions.
+    fn crate_multiset(&self) -> Vec<char> {
+        let mut crates = self.stacks.concat();
+        crates.sort_unstable();
+        crates
+    }
+}
+
+/// Formats one stack per line, from bottom to top, eg. `1: ZN`.
+impl fmt::Display for CrateStacks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.stacks
+            .iter()
+            .enumerate()
+            .try_for_each(|(index, stack)| {
+                writeln!(f, "{}: {}", index + 1, stack.iter().collect::<String>())
+            })
+    }
+}
+
+/// Plays all moves of `move_list` on a copy of `crate_stacks` with `play_move`, and returns the
+/// final state. Crates are only ever moved around, which is checked in debug builds.
+fn simulate(
+    crate_stacks: &CrateStacks,
+    move_list: &[MoveCommand],
+    play_move: fn(&mut CrateStacks, &MoveCommand),
+) -> CrateStacks {
+    let mut simulation_stack = crate_stacks.clone();
+    move_list
+        .iter()
+        .for_each(|m| play_move(&mut simulation_stack, m));
+    debug_assert_eq!(
+        simulation_stack.total_crates(),
+        crate_stacks.total_crates(),
+        "crates were created or lost"
+    );
+
+    simulation_stack
+}
+
+/// Same as `simulate`, but returns an error if the crates of the final state aren't those of
+/// `crate_stacks`, which would mean that parsing or playing the moves went wrong.
+fn simulate_checked(
+    crate_stacks: &CrateStacks,
+    move_list: &[MoveCommand],
+    play_move: fn(&mut CrateStacks, &MoveCommand),
+) -> Result<CrateStacks> {
+    let final_stacks = simulate(crate_stacks, move_list, play_move);
+    let (before, after) = (crate_stacks.crate_multiset(), final_stacks.crate_multiset());
+    if before != after {
+        return Err(anyhow!(
+            "crates changed: {} before the moves, {} after",
+            before.iter().collect::<String>(),
+            after.iter().collect::<String>()
+        ));
+    }
+    Ok(final_stacks)
+}
+
+/// Plays all moves of `move_list` on a copy of `crate_stacks` with `mover`, and returns the crates
+/// moved by each of them, in their order on the destination stack.
+fn trace_moves(
+    crate_stacks: &CrateStacks,
+    move_list: &[MoveCommand],
+    mover: CrateMover,
+) -> Vec<Vec<char>> {
+    let mut simulation_stack = crate_stacks.clone();
+    move_list
+        .iter()
+        .map(|m| simulation_stack.play_move(m, mover).to_vec())
+        .collect()
 }
 
 impl FromStr for CrateStacks {
     type Err = anyhow::Error;
 
+    /// Parses a drawing of the crate stacks, optionally ending with the line of stack indexes.
+    ///
+    /// Each stack takes 4 characters on a line (`[X] `), so the stack count is derived from the
+    /// widest line of crates. A crate is an alphanumeric label between any two delimiters, such as
+    /// `[X]`, `(X)` or `{X}`. The index line, if any, is skipped: it can't add stacks.
     fn from_str(s: &str) -> Result<Self> {
-        let mut lines = s.lines().collect::<Vec<_>>();
-        let indexes = lines.pop().expect("unexpected crate stack syntax");
-        let stack_count = indexes
-            .split(' ')
-            .last()
-            .expect("unexpected index line syntax")
-            .parse::<usize>()
-            .expect("unexpected index format");
+        let mut lines = s
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+        let is_index_line = |line: &str| line.chars().all(|c| c.is_ascii_digit() || c == ' ');
+        if lines.last().is_some_and(|line| is_index_line(line)) {
+            lines.pop();
+        }
+        let max_width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or_default();
+        let stack_count = max_width.div_ceil(4);
+        if stack_count == 0 {
+            return Err(anyhow!("unexpected crate stack syntax"));
+        }
         let mut stacks = vec![vec![]; stack_count];
 
-        s.lines().rev().skip(1).for_each(|line| {
-            for i in 0..stack_count {
-                let pos = 1 + i * 4;
-                match line.chars().nth(pos) {
-                    None | Some(' ') => continue,
-                    Some(c) => stacks[i].push(c),
+        for line in lines.iter().rev() {
+            let line = line.chars().collect::<Vec<_>>();
+            for (i, stack) in stacks.iter_mut().enumerate() {
+                let cell = &line[(i * 4).min(line.len())..(i * 4 + 3).min(line.len())];
+                match cell {
+                    _ if cell.iter().all(|c| *c == ' ') => continue,
+                    [open, label, close]
+                        if *open != ' ' && *close != ' ' && label.is_alphanumeric() =>
+                    {
+                        stack.push(*label)
+                    }
+                    _ => {
+                        return Err(anyhow!(
+                            "invalid crate {:?} in stack {}",
+                            cell.iter().collect::<String>(),
+                            i + 1
+                        ))
+                    }
                 };
             }
-        });
+        }
 
         Ok(CrateStacks { stacks })
     }
 }
 
-fn main() {
-    let input = include_str!("../../puzzles/day05.prod");
-    let (crate_stacks_initial_state, move_list) = input.split_once("\n\n").expect("invalid input");
+/// The whole puzzle input: the drawing of the crate stacks, a blank line, and the list of moves.
+#[derive(Debug)]
+struct Puzzle {
+    stacks: CrateStacks,
+    moves: Vec<MoveCommand>,
+}
+
+/// Formats the stacks as `CrateStacks` does, a blank line, and one move per line.
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.stacks)?;
+        self.moves
+            .iter()
+            .try_for_each(|move_cmd| writeln!(f, "{}", move_cmd))
+    }
+}
 
-    let crate_stacks = crate_stacks_initial_state
-        .parse::<CrateStacks>()
-        .expect("failed to parse initial state");
+impl FromStr for Puzzle {
+    type Err = anyhow::Error;
 
-    let mut simulation_cratemover_9000_stack = crate_stacks.clone();
-    move_list.lines().map(MoveCommand::from_str).for_each(|m| {
-        simulation_cratemover_9000_stack
-            .play_move_with_cratemover_9000(&m.expect("failed to parse move"))
-    });
-    println!("{:?}", simulation_cratemover_9000_stack.get_top_crates());
+    fn from_str(s: &str) -> Result<Self> {
+        let (drawing, move_list) = s
+            .split_once("\n\n")
+            .ok_or_else(|| anyhow!("missing blank line between the drawing and the moves"))?;
+        let stacks = drawing.parse().context("failed to parse the drawing")?;
+        let moves = move_list
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                line.parse()
+                    .with_context(|| format!("failed to parse move #{}: {:?}", index + 1, line))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Puzzle { stacks, moves })
+    }
+}
+
+#[derive(Parser)]
+struct CmdlineArgs {
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    runner: RunnerArgs,
 
-    let mut simulation_cratemover_9001_stack = crate_stacks.clone();
-    move_list.lines().map(MoveCommand::from_str).for_each(|m| {
-        simulation_cratemover_9001_stack
-            .play_move_with_cratemover_9001(&m.expect("failed to parse move"))
+    // Print the height of each stack, before and after the moves, instead of solving the puzzle.
+    #[clap(long = "stats")]
+    stats: bool,
+
+    // Print the crates relocated by each move, in their resulting order, with both the CrateMover
+    // 9000 and 9001 instead of solving the puzzle.
+    #[clap(long = "explain-move")]
+    explain_move: bool,
+
+    // Check that the moves preserve the crates with both the CrateMover 9000 and 9001 before
+    // solving the puzzle. Always done in debug builds.
+    #[clap(long = "check")]
+    check: bool,
+}
+
+fn main() {
+    let cmdline_args = CmdlineArgs::parse();
+    let _log = cmdline_args.runner.init_logging();
+    let puzzle: Puzzle = log::in_span("parse", || {
+        cmdline_args
+            .input
+            .load(5)
+            .expect("failed to read puzzle input")
+            .parse()
+            .expect("failed to parse puzzle")
     });
+    if cmdline_args.runner.print_parsed(|| puzzle.to_string()) {
+        return;
+    }
+    let Puzzle {
+        stacks: crate_stacks,
+        moves: move_list,
+    } = puzzle;
+    log_debug!(
+        stacks = crate_stacks.stacks.len(),
+        crates = crate_stacks.total_crates(),
+        moves = move_list.len(),
+        "parsed puzzle"
+    );
+
+    if cmdline_args.stats {
+        let simulations = [
+            ("initial", crate_stacks.clone()),
+            (
+                "cratemover 9000",
+                simulate(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9000),
+            ),
+            (
+                "cratemover 9001",
+                simulate(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9001),
+            ),
+        ];
+        for (label, stacks) in simulations.iter() {
+            println!("{}: {:?} (total: {})", label, stacks.stack_heights(), stacks.total_crates());
+        }
+        return;
+    }
 
-    println!("{:?}", simulation_cratemover_9001_stack.get_top_crates());
+    if cmdline_args.explain_move {
+        let movers = [CrateMover::Model9000, CrateMover::Model9001];
+        let traces = movers.map(|mover| trace_moves(&crate_stacks, &move_list, mover));
+        for (index, move_cmd) in move_list.iter().enumerate() {
+            for (mover, trace) in movers.iter().zip(traces.iter()) {
+                let moved = trace[index].iter().map(char::to_string).collect::<Vec<_>>();
+                println!("{}: [{}] ({} {})", move_cmd, moved.join(","), mover, mover.order());
+            }
+        }
+        return;
+    }
+
+    if cmdline_args.check || cfg!(debug_assertions) {
+        simulate_checked(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9000)
+            .expect("the CrateMover 9000 changed the crates");
+        simulate_checked(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9001)
+            .expect("the CrateMover 9001 changed the crates");
+    }
+
+    runner::run(
+        &cmdline_args.runner,
+        || {
+            simulate(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9000)
+                .get_top_crates()
+        },
+        || {
+            simulate(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9001)
+                .get_top_crates()
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("../../puzzles/day05.test");
+
+    #[test]
+    fn parse_sample_puzzle() {
+        let puzzle = SAMPLE.parse::<Puzzle>().unwrap();
+
+        assert_eq!(puzzle.moves.len(), 4);
+        assert_eq!(puzzle.stacks.get_top_crates(), "NDP");
+    }
+
+    #[test]
+    fn parse_puzzle_errors() {
+        let err = "[A]\n 1".parse::<Puzzle>().err().unwrap();
+        assert_eq!(err.to_string(), "missing blank line between the drawing and the moves");
+
+        let err = "\n\nmove 1 from 1 to 2".parse::<Puzzle>().err().unwrap();
+        assert_eq!(err.to_string(), "failed to parse the drawing");
+
+        let err = "[A]\n 1\n\nmove 1 from 1 to 2\nmove x"
+            .parse::<Puzzle>()
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "failed to parse move #2: \"move x\"");
+    }
+
+    #[test]
+    fn display_sample_puzzle() {
+        let puzzle = SAMPLE.parse::<Puzzle>().unwrap();
+
+        assert_eq!(
+            puzzle.to_string(),
+            "1: ZN\n2: MCD\n3: P\n\n\
+             move 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2\n"
+        );
+    }
+
+    #[test]
+    fn stack_heights_sample() {
+        let crate_stacks = SAMPLE.parse::<Puzzle>().unwrap().stacks;
+
+        assert_eq!(crate_stacks.stack_heights(), vec![2, 3, 1]);
+        assert_eq!(crate_stacks.total_crates(), 6);
+    }
+
+    #[test]
+    fn moves_preserve_total_crates() {
+        let Puzzle {
+            stacks: crate_stacks,
+            moves: move_list,
+        } = SAMPLE.parse().unwrap();
+
+        let cratemover_9000 =
+            simulate(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9000);
+        assert_eq!(cratemover_9000.stack_heights(), vec![1, 1, 4]);
+        assert_eq!(cratemover_9000.total_crates(), 6);
+
+        let cratemover_9001 =
+            simulate(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9001);
+        assert_eq!(cratemover_9001.stack_heights(), vec![1, 1, 4]);
+        assert_eq!(cratemover_9001.total_crates(), 6);
+    }
+
+    #[test]
+    fn parse_move_all() {
+        let move_cmd = "move all from 2 to 1".parse::<MoveCommand>().unwrap();
+        assert_eq!(move_cmd.crate_count, CrateCount::All);
+        assert_eq!((move_cmd.src_index, move_cmd.dst_index), (2, 1));
+
+        let move_cmd = "move 3 from 1 to 3".parse::<MoveCommand>().unwrap();
+        assert_eq!(move_cmd.crate_count, CrateCount::Count(3));
+
+        assert!("move ALL from 2 to 1".parse::<MoveCommand>().is_err());
+    }
+
+    #[test]
+    fn move_command_round_trip() {
+        let (_, move_list) = SAMPLE.split_once("\n\n").unwrap();
+        for line in move_list.lines().chain(["move all from 2 to 1"]) {
+            let move_cmd = line.parse::<MoveCommand>().unwrap();
+            assert_eq!(move_cmd.to_string(), line);
+            assert_eq!(move_cmd.to_string().parse::<MoveCommand>().unwrap(), move_cmd);
+        }
+    }
+
+    #[test]
+    fn play_move_all() {
+        let crate_stacks = SAMPLE.parse::<Puzzle>().unwrap().stacks;
+        let move_list = ["move all from 2 to 1", "move all from 3 to 1"]
+            .iter()
+            .map(|line| line.parse().unwrap())
+            .collect::<Vec<MoveCommand>>();
+
+        let cratemover_9000 =
+            simulate(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9000);
+        assert_eq!(
+            cratemover_9000.stacks,
+            vec![vec!['Z', 'N', 'D', 'C', 'M', 'P'], vec![], vec![]]
+        );
+
+        let cratemover_9001 =
+            simulate(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9001);
+        assert_eq!(
+            cratemover_9001.stacks,
+            vec![vec!['Z', 'N', 'M', 'C', 'D', 'P'], vec![], vec![]]
+        );
+    }
+
+    #[test]
+    fn moves_preserve_crate_multiset() {
+        let puzzle = SAMPLE.parse::<Puzzle>().unwrap();
+
+        for play_move in [
+            CrateStacks::play_move_with_cratemover_9000,
+            CrateStacks::play_move_with_cratemover_9001,
+        ] {
+            let final_stacks = simulate_checked(&puzzle.stacks, &puzzle.moves, play_move).unwrap();
+            assert_eq!(final_stacks.crate_multiset(), vec!['C', 'D', 'M', 'N', 'P', 'Z']);
+        }
+    }
+
+    #[test]
+    fn corrupted_move_changes_crate_multiset() {
+        let puzzle = SAMPLE.parse::<Puzzle>().unwrap();
+        // Relabels the moved crates, keeping their number.
+        let corrupted_move = |stacks: &mut CrateStacks, move_cmd: &MoveCommand| {
+            stacks.play_move_with_cratemover_9001(move_cmd);
+            if let Some(top) = stacks.stacks[move_cmd.dst_index - 1].last_mut() {
+                *top = 'X';
+            }
+        };
+
+        let err = simulate_checked(&puzzle.stacks, &puzzle.moves, corrupted_move).unwrap_err();
+        assert_eq!(err.to_string(), "crates changed: CDMNPZ before the moves, MNPXXZ after");
+    }
+
+    #[test]
+    fn trace_multi_crate_move() {
+        let puzzle = SAMPLE.parse::<Puzzle>().unwrap();
+        let cratemover_9000 = trace_moves(&puzzle.stacks, &puzzle.moves, CrateMover::Model9000);
+        let cratemover_9001 = trace_moves(&puzzle.stacks, &puzzle.moves, CrateMover::Model9001);
+
+        // `move 3 from 1 to 3` picks up Z, N and D, from bottom to top.
+        assert_eq!(puzzle.moves[1].to_string(), "move 3 from 1 to 3");
+        assert_eq!(cratemover_9000[1], vec!['D', 'N', 'Z']);
+        assert_eq!(cratemover_9001[1], vec!['Z', 'N', 'D']);
+
+        // Single crate moves are the same with both models, until the stacks diverge.
+        assert_eq!(cratemover_9000[0], cratemover_9001[0]);
+        assert_eq!(cratemover_9000.len(), puzzle.moves.len());
+    }
+
+    #[test]
+    fn play_move_all_from_empty_stack() {
+        let mut crate_stacks = "[A]     [B]\n 1   2   3".parse::<CrateStacks>().unwrap();
+        let move_cmd = "move all from 2 to 1".parse::<MoveCommand>().unwrap();
+
+        crate_stacks.play_move_with_cratemover_9000(&move_cmd);
+        crate_stacks.play_move_with_cratemover_9001(&move_cmd);
+        assert_eq!(crate_stacks.stack_heights(), vec![1, 0, 1]);
+    }
+
+    #[test]
+    fn top_crates_after_move_all() {
+        let crate_stacks = SAMPLE.parse::<Puzzle>().unwrap().stacks;
+        let move_list = vec!["move all from 2 to 1".parse::<MoveCommand>().unwrap()];
+
+        let cratemover_9000 =
+            simulate(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9000);
+        assert_eq!(cratemover_9000.get_top_crates(), "MP");
+
+        let cratemover_9001 =
+            simulate(&crate_stacks, &move_list, CrateStacks::play_move_with_cratemover_9001);
+        assert_eq!(cratemover_9001.get_top_crates(), "DP");
+    }
+
+    #[test]
+    fn parse_without_index_line() {
+        let crate_stacks = "    [D]\n[N] [C]\n[Z] [M] [P]"
+            .parse::<CrateStacks>()
+            .unwrap();
+
+        assert_eq!(crate_stacks.stack_heights(), vec![2, 3, 1]);
+        assert_eq!(crate_stacks.get_top_crates(), "NDP");
+    }
+
+    #[test]
+    fn parse_with_trailing_whitespace() {
+        let drawing = "    [D]        \n[N] [C]    \n[Z] [M] [P]   \n 1   2   3 \n";
+        let crate_stacks = drawing.parse::<CrateStacks>().unwrap();
+
+        assert_eq!(crate_stacks.stack_heights(), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn parse_with_misaligned_index_line() {
+        let crate_stacks = "[A]     [B]\n1 2 3 4".parse::<CrateStacks>().unwrap();
+
+        // The stacks are counted from the crates only, so the extra index adds no empty stack.
+        assert_eq!(crate_stacks.stack_heights(), vec![1, 0, 1]);
+        assert_eq!(crate_stacks.get_top_crates(), "AB");
+    }
+
+    #[test]
+    fn parse_alternate_brackets() {
+        let brackets = "    [D]\n[N] [C]\n[Z] [M] [P]"
+            .parse::<CrateStacks>()
+            .unwrap();
+        let parens = "    (D)\n(N) (C)\n(Z) (M) (P)"
+            .parse::<CrateStacks>()
+            .unwrap();
+        let mixed = "    {D}\n(N) [C]\n{Z} (M) <P>"
+            .parse::<CrateStacks>()
+            .unwrap();
+
+        assert_eq!(parens.stacks, brackets.stacks);
+        assert_eq!(mixed.stacks, brackets.stacks);
+    }
+
+    #[test]
+    fn parse_invalid_crates() {
+        let err = "[A] [-]".parse::<CrateStacks>().err().unwrap();
+        assert_eq!(err.to_string(), "invalid crate \"[-]\" in stack 2");
+
+        let err = "[A]  B ".parse::<CrateStacks>().err().unwrap();
+        assert_eq!(err.to_string(), "invalid crate \" B\" in stack 2");
+
+        let err = "[A] [B".parse::<CrateStacks>().err().unwrap();
+        assert_eq!(err.to_string(), "invalid crate \"[B\" in stack 2");
+    }
+
+    #[test]
+    fn parse_empty_drawing() {
+        assert!("".parse::<CrateStacks>().is_err());
+        assert!("\n \n".parse::<CrateStacks>().is_err());
+    }
+
+    #[cfg(feature = "gen")]
+    #[test]
+    fn generated_input_parses() {
+        use advent_of_code_2022::generate;
+
+        for seed in 0..10 {
+            let input = generate::generate(5, seed, 100).unwrap();
+            let puzzle = input.parse::<Puzzle>().unwrap();
+            for play_move in [
+                CrateStacks::play_move_with_cratemover_9000,
+                CrateStacks::play_move_with_cratemover_9001,
+            ] {
+                let stacks = simulate(&puzzle.stacks, &puzzle.moves, play_move);
+                assert_eq!(stacks.get_top_crates().len(), stacks.stacks.len());
+            }
+        }
+    }
 }
@@ -1,48 +1,299 @@
-use std::collections::HashSet;
+extern crate advent_of_code_2022;
+extern crate clap;
+#[cfg(test)]
+extern crate proptest;
+
+use std::collections::{HashMap, HashSet};
+use std::iter;
+
+use advent_of_code_2022::input::InputArgs;
+use advent_of_code_2022::runner::{self, RunnerArgs};
+use clap::Parser;
+
+/// An iterator over the substrings of `size` characters of a string, sliding one character at a
+/// time.
+struct CharWindows<'a> {
+    stream: &'a str,
+    size: usize,
+}
+
+impl<'a> CharWindows<'a> {
+    fn new(stream: &'a str, size: usize) -> Self {
+        assert!(size > 0);
+        CharWindows { stream, size }
+    }
+}
+
+impl<'a> Iterator for CharWindows<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The byte offset of the end of the window, if the stream is long enough.
+        let end = self
+            .stream
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(iter::once(self.stream.len()))
+            .nth(self.size)?;
+        let window = &self.stream[..end];
+        let first_char_len = window.chars().next()?.len_utf8();
+        self.stream = &self.stream[first_char_len..];
+
+        Some(window)
+    }
+}
 
-fn has_duplicates<const N: usize>(buf: &[char; N]) -> bool {
+fn has_duplicates(window: &str) -> bool {
     let mut seen = HashSet::new();
-    for i in 0..N {
-        if seen.contains(&buf[i]) {
+    for c in window.chars() {
+        if seen.contains(&c) {
             return true;
         }
-        seen.insert(buf[i]);
+        seen.insert(c);
     }
     false
 }
 
+/// Same as `has_duplicates`, but tracks the characters seen in a bitmask instead of a `HashSet`,
+/// which is much faster. Falls back to `has_duplicates` for windows with non-ASCII characters.
+fn has_duplicates_bitmask(window: &str) -> bool {
+    if !window.is_ascii() {
+        return has_duplicates(window);
+    }
+    let mut seen = 0u128;
+    for byte in window.bytes() {
+        let bit = 1 << byte;
+        if seen & bit != 0 {
+            return true;
+        }
+        seen |= bit;
+    }
+    false
+}
+
+/// Returns the number of characters read until the end of the first window of `N` distinct
+/// characters.
 fn find_first_marker<const N: usize>(stream: &str) -> Option<usize> {
-    assert!(stream.len() >= N);
+    find_first_marker_with::<N>(stream, has_duplicates_bitmask)
+}
+
+/// Same as `find_first_marker`, with `has_duplicates` telling whether a window repeats a
+/// character.
+fn find_first_marker_with<const N: usize>(
+    stream: &str,
+    has_duplicates: fn(&str) -> bool,
+) -> Option<usize> {
+    CharWindows::new(stream, N)
+        .position(|window| !has_duplicates(window))
+        .map(|pos| pos + N)
+}
+
+/// Returns the number of characters read until the end of the first window of `window` characters
+/// containing exactly `distinct` distinct characters.
+///
+/// Keeps a frequency table of the characters in the window as it slides, so each step is constant
+/// time. [`find_first_marker`] is the special case `distinct == window`.
+fn first_window_with_distinct(stream: &str, window: usize, distinct: usize) -> Option<usize> {
+    scan_marker_positions(stream, window, distinct).end
+}
 
-    let mut buf = [char::default(); N];
-    let mut idx = 0;
+/// The largest window size searched by `min_window_with_marker`: the size of the alphabet of the
+/// puzzle inputs.
+const MAX_MARKER_WINDOW: usize = 26;
 
-    let mut iter = stream.chars().enumerate();
-    for i in 0..N {
-        (_, buf[i]) = iter.next()?;
+/// Returns the smallest window size, up to `MAX_MARKER_WINDOW`, for which `stream` has a marker,
+/// ie. a window of that many distinct characters, or `None` if there is none.
+///
+/// Tries increasing window sizes with `first_window_with_distinct`.
+fn min_window_with_marker(stream: &str) -> Option<usize> {
+    (1..=MAX_MARKER_WINDOW).find(|&size| first_window_with_distinct(stream, size, size).is_some())
+}
+
+/// The outcome of scanning a stream for a window with a given number of distinct characters.
+#[derive(Debug, PartialEq)]
+struct MarkerScan {
+    /// The number of characters read until the end of the matching window, if any.
+    end: Option<usize>,
+    /// The last window examined: the matching one, or the one at the end of the stream. Shorter
+    /// than the window size if the stream is.
+    window: String,
+    /// The number of distinct characters in `window`.
+    distinct: usize,
+}
+
+impl MarkerScan {
+    /// Describes the matching window, or the last window examined if there is none.
+    fn debug(&self) -> String {
+        match self.end {
+            Some(end) => format!("marker {:?} ends at {}", self.window, end),
+            None => format!(
+                "no marker, last window {:?} has {} distinct characters",
+                self.window, self.distinct
+            ),
+        }
     }
+}
 
-    while let Some((pos, c)) = iter.next() {
-        if !has_duplicates(&buf) {
-            return Some(pos);
+/// Same as `first_window_with_distinct`, but also returns the state of the window where the scan
+/// stopped.
+fn scan_marker_positions(stream: &str, window: usize, distinct: usize) -> MarkerScan {
+    assert!(window > 0);
+    let chars = stream.chars().collect::<Vec<_>>();
+    let mut frequencies = HashMap::new();
+    let mut end = None;
+
+    for (pos, &c) in chars.iter().enumerate() {
+        *frequencies.entry(c).or_insert(0) += 1;
+        if pos >= window {
+            let evicted = chars[pos - window];
+            let count = frequencies.get_mut(&evicted).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                frequencies.remove(&evicted);
+            }
+        }
+        if pos + 1 >= window && frequencies.len() == distinct {
+            end = Some(pos + 1);
+            break;
         }
-        buf[idx] = c;
-        idx = (idx + 1) % N;
     }
 
-    None
+    let stop = end.unwrap_or(chars.len());
+    MarkerScan {
+        end,
+        window: chars[stop.saturating_sub(window)..stop].iter().collect(),
+        distinct: frequencies.len(),
+    }
+}
+
+#[derive(Parser)]
+struct CmdlineArgs {
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    runner: RunnerArgs,
+
+    // Find the first window of this size with exactly `--distinct` distinct characters instead.
+    #[clap(long = "window", requires = "distinct", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    window: Option<usize>,
+
+    // The number of distinct characters to look for with `--window`.
+    #[clap(long = "distinct", requires = "window")]
+    distinct: Option<usize>,
+
+    // Print the matching window, or the last window examined if there is none, instead of solving
+    // the puzzle.
+    #[clap(long = "debug")]
+    debug: bool,
+
+    // Print the smallest window size for which the stream has a marker instead of solving the
+    // puzzle.
+    #[clap(long = "bisect")]
+    bisect: bool,
 }
 
 fn main() {
-    let input = include_str!("../../puzzles/day06.prod");
+    let cmdline_args = CmdlineArgs::parse();
+    let _log = cmdline_args.runner.init_logging();
+    let input = &cmdline_args
+        .input
+        .load(6)
+        .expect("failed to read puzzle input");
+    if cmdline_args
+        .runner
+        .print_parsed(|| format!("{} characters\n{}", input.trim_end().len(), input.trim_end()))
+    {
+        return;
+    }
+
+    if cmdline_args.debug {
+        let sizes = match (cmdline_args.window, cmdline_args.distinct) {
+            (Some(window), Some(distinct)) => vec![(window, distinct)],
+            _ => vec![(4, 4), (14, 14)],
+        };
+        for (window, distinct) in sizes {
+            let scan = scan_marker_positions(input, window, distinct);
+            println!("{} of {}: {}", distinct, window, scan.debug());
+        }
+        return;
+    }
+
+    if cmdline_args.bisect {
+        match min_window_with_marker(input) {
+            Some(size) => println!("{}", size),
+            None => println!("No marker of up to {} characters", MAX_MARKER_WINDOW),
+        }
+        return;
+    }
 
-    println!("{:?}", find_first_marker::<4>(input).expect("marker not found"));
-    println!("{:?}", find_first_marker::<14>(input).expect("marker not found"));
+    if let (Some(window), Some(distinct)) = (cmdline_args.window, cmdline_args.distinct) {
+        match first_window_with_distinct(input, window, distinct) {
+            Some(pos) => println!("{pos}"),
+            None => println!("No window of {window} with {distinct} distinct characters"),
+        }
+        return;
+    }
+
+    runner::run(
+        &cmdline_args.runner,
+        || find_first_marker::<4>(input).expect("marker not found"),
+        || find_first_marker::<14>(input).expect("marker not found"),
+    );
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn char_windows() {
+        let windows = CharWindows::new("abcd", 2).collect::<Vec<_>>();
+        assert_eq!(windows, ["ab", "bc", "cd"]);
+
+        assert_eq!(CharWindows::new("abcd", 4).collect::<Vec<_>>(), ["abcd"]);
+        assert_eq!(CharWindows::new("abcd", 5).next(), None);
+        assert_eq!(CharWindows::new("", 1).next(), None);
+    }
+
+    #[test]
+    fn char_windows_multibyte() {
+        let windows = CharWindows::new("aéb", 2).collect::<Vec<_>>();
+        assert_eq!(windows, ["aé", "éb"]);
+    }
+
+    #[test]
+    fn has_duplicates_implementations_agree() {
+        for window in [
+            "abcd",
+            "abca",
+            "aa",
+            "a",
+            "zyxwvutsrqponm",
+            "zyxwvutsrqponz",
+            "aéb",
+            "éaé",
+        ] {
+            assert_eq!(has_duplicates_bitmask(window), has_duplicates(window), "{}", window);
+        }
+
+        let input = include_str!("../../puzzles/day06.prod");
+        assert_eq!(
+            find_first_marker_with::<4>(input, has_duplicates),
+            find_first_marker_with::<4>(input, has_duplicates_bitmask)
+        );
+        assert_eq!(
+            find_first_marker_with::<14>(input, has_duplicates),
+            find_first_marker_with::<14>(input, has_duplicates_bitmask)
+        );
+    }
+
+    #[test]
+    fn marker_at_end_of_stream() {
+        assert_eq!(find_first_marker::<4>("aaaabcd"), Some(7));
+        assert_eq!(find_first_marker::<4>("aaaabcc"), None);
+    }
 
     #[test]
     fn start_of_packet_test() {
@@ -60,4 +311,114 @@ mod tests {
         assert_eq!(find_first_marker::<14>("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), Some(29));
         assert_eq!(find_first_marker::<14>("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), Some(26));
     }
+
+    #[test]
+    fn window_with_fewer_distinct() {
+        assert_eq!(first_window_with_distinct("abcabc", 3, 2), None);
+        assert_eq!(first_window_with_distinct("abcaab", 3, 2), Some(5));
+        assert_eq!(first_window_with_distinct("aaab", 2, 1), Some(2));
+        assert_eq!(first_window_with_distinct("abab", 3, 2), Some(3));
+        assert_eq!(first_window_with_distinct("abcd", 4, 1), None);
+        assert_eq!(first_window_with_distinct("ab", 3, 2), None);
+    }
+
+    #[test]
+    fn window_with_all_distinct_matches_marker() {
+        for stream in [
+            "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+            "bvwbjplbgvbhsrlpgdmjqwftvncz",
+            "nppdvjthqldpwncqszvftbrmjlhg",
+            "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+            "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+        ] {
+            assert_eq!(first_window_with_distinct(stream, 4, 4), find_first_marker::<4>(stream));
+            assert_eq!(first_window_with_distinct(stream, 14, 14), find_first_marker::<14>(stream));
+        }
+    }
+
+    #[test]
+    fn scan_marker_positions_debug() {
+        let scan = scan_marker_positions("abcabcabcd", 4, 4);
+        assert_eq!(scan.end, Some(10));
+        assert_eq!(scan.debug(), "marker \"abcd\" ends at 10");
+
+        let scan = scan_marker_positions("abcabcabca", 4, 4);
+        assert_eq!(
+            scan,
+            MarkerScan {
+                end: None,
+                window: "abca".to_owned(),
+                distinct: 3,
+            }
+        );
+        assert_eq!(scan.debug(), "no marker, last window \"abca\" has 3 distinct characters");
+
+        let scan = scan_marker_positions("ab", 4, 4);
+        assert_eq!(scan.debug(), "no marker, last window \"ab\" has 2 distinct characters");
+    }
+
+    #[test]
+    fn min_window_with_marker_early_repeats() {
+        assert_eq!(min_window_with_marker(""), None);
+        assert_eq!(min_window_with_marker("a"), Some(1));
+        assert_eq!(min_window_with_marker("aaaa"), Some(1));
+        assert_eq!(min_window_with_marker("aabbaabb"), Some(1));
+        assert_eq!(min_window_with_marker("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), Some(1));
+    }
+
+    #[cfg(feature = "gen")]
+    #[test]
+    fn generated_input_parses() {
+        use advent_of_code_2022::generate;
+
+        for seed in 0..10 {
+            let input = generate::generate(6, seed, 100).unwrap();
+            assert!(find_first_marker::<4>(&input).is_some());
+            assert!(find_first_marker::<14>(&input).is_some());
+        }
+    }
+
+    /// Returns the end of the first window of `window` distinct characters by checking every
+    /// window from the start of the stream.
+    fn brute_force_marker(stream: &str, window: usize) -> Option<usize> {
+        let chars = stream.chars().collect::<Vec<_>>();
+        (window..=chars.len()).find(|&end| {
+            let window = &chars[end - window..end];
+            window
+                .iter()
+                .enumerate()
+                .all(|(i, c)| !window[i + 1..].contains(c))
+        })
+    }
+
+    proptest! {
+        // A 12 letters alphabet makes windows of up to 10 distinct characters neither certain nor
+        // impossible.
+        #[test]
+        fn marker_is_minimal(stream in "[a-l]{0,64}", window in 1usize..=10) {
+            let expected = brute_force_marker(&stream, window);
+
+            prop_assert_eq!(first_window_with_distinct(&stream, window, window), expected);
+            if window == 4 {
+                prop_assert_eq!(find_first_marker::<4>(&stream), expected);
+            }
+            if window == 10 {
+                prop_assert_eq!(find_first_marker::<10>(&stream), expected);
+            }
+        }
+
+        #[test]
+        fn min_window_with_marker_is_minimal(stream in "[a-l]{0,64}") {
+            let expected = (1..=MAX_MARKER_WINDOW)
+                .find(|&size| brute_force_marker(&stream, size).is_some());
+
+            prop_assert_eq!(min_window_with_marker(&stream), expected);
+        }
+
+        #[test]
+        fn start_of_packet_marker_is_minimal(stream in "[a-z]{0,64}") {
+            prop_assert_eq!(find_first_marker::<4>(&stream), brute_force_marker(&stream, 4));
+            prop_assert_eq!(find_first_marker::<14>(&stream), brute_force_marker(&stream, 14));
+        }
+    }
 }
@@ -1,7 +1,26 @@
 use std::cell::{Ref, RefCell};
+use std::fmt::Write;
+use std::fs;
+use std::iter;
+use std::path::PathBuf;
 use std::rc::Rc;
 
+extern crate advent_of_code_2022;
 extern crate anyhow;
+extern crate clap;
+#[cfg(test)]
+extern crate insta;
+extern crate serde;
+#[cfg(test)]
+extern crate serde_json;
+
+use advent_of_code_2022::input::InputArgs;
+use advent_of_code_2022::runner::{self, RunnerArgs};
+use advent_of_code_2022::viz::{self, Style};
+use advent_of_code_2022::{log, log_debug};
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
 
 /// A filesystem and its root node.
 struct Filesystem<'fs> {
@@ -45,27 +64,139 @@ impl<'fs> FsNode<'fs> {
         }
     }
 
-    /// Finds a child node by its name, and returns it. Panics if the child does not exist.
-    fn get_child_by_name(&self, child_name: &str) -> Rc<RefCell<FsNode<'fs>>> {
+    fn name(&self) -> &'fs str {
         match self {
-            FsNode::File { .. } => panic!("a file has no children"),
-            FsNode::Directory { children, .. } => {
-                for child in children.iter() {
-                    if let FsNode::Directory { name, .. } = *child.borrow_mut() {
-                        if name == child_name {
-                            return child.clone();
-                        }
-                    }
+            FsNode::File { name, .. } | FsNode::Directory { name, .. } => name,
+        }
+    }
+
+    /// Returns a copy of the node that shares none of its children with the original.
+    fn deep_copy(&self) -> FsNode<'fs> {
+        match self {
+            FsNode::File { name, size } => FsNode::File { name, size: *size },
+            FsNode::Directory { name, children } => FsNode::Directory {
+                name,
+                children: children
+                    .iter()
+                    .map(|child| Rc::new(RefCell::new(child.borrow().deep_copy())))
+                    .collect(),
+            },
+        }
+    }
+
+    /// Merges `other` into this node. The children of two directories are merged by name, and any
+    /// other node is replaced by a copy of `other`, which is considered the latest.
+    fn merge(&mut self, other: &FsNode<'fs>) {
+        if let (
+            FsNode::Directory { children, .. },
+            FsNode::Directory {
+                children: other_children,
+                ..
+            },
+        ) = (&mut *self, other)
+        {
+            for other_child in other_children {
+                let other_child = other_child.borrow();
+                match children
+                    .iter()
+                    .find(|child| child.borrow().name() == other_child.name())
+                {
+                    Some(child) => child.borrow_mut().merge(&other_child),
+                    None => children.push(Rc::new(RefCell::new(other_child.deep_copy()))),
                 }
-                panic!("child not found: {:?}", child_name);
             }
+            return;
+        }
+        *self = other.deep_copy();
+    }
+
+    /// Finds a child directory by its name, and returns it.
+    fn get_child_by_name(&self, child_name: &str) -> Result<Rc<RefCell<FsNode<'fs>>>> {
+        match self {
+            FsNode::File { .. } => Err(anyhow!("a file has no children")),
+            FsNode::Directory { children, .. } => children
+                .iter()
+                .find(|child| match *child.borrow() {
+                    FsNode::Directory { name, .. } => name == child_name,
+                    FsNode::File { .. } => false,
+                })
+                .cloned()
+                .ok_or_else(|| anyhow!("child not found: {:?}", child_name)),
+        }
+    }
+
+    fn push_child(&mut self, child: Rc<RefCell<FsNode<'fs>>>) -> Result<()> {
+        match self {
+            FsNode::File { .. } => Err(anyhow!("cannot push child to a file")),
+            FsNode::Directory { children, .. } => {
+                children.push(child);
+                Ok(())
+            }
+        }
+    }
+
+    /// Describes the node the way the puzzle statement lists it, eg. `- a (dir)`.
+    fn label(&self) -> String {
+        match self {
+            FsNode::File { name, size } => format!("- {} (file, size={})", name, size),
+            FsNode::Directory { name, .. } => format!("- {} (dir)", name),
         }
     }
+}
+
+/// Structural statistics of a filesystem.
+#[derive(Debug, PartialEq)]
+struct FsStats<'fs> {
+    files: usize,
+    /// The number of directories, root included.
+    directories: usize,
+    /// The depth of the deepest node, the root being at depth 0.
+    max_depth: usize,
+    /// The name and size of the largest file, if any.
+    largest_file: Option<(&'fs str, usize)>,
+}
 
-    fn push_child(&mut self, child: Rc<RefCell<FsNode<'fs>>>) {
+/// A deep copy of a `FsNode` made of plain data, for read-only analysis without borrowing.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[cfg_attr(not(test), allow(dead_code))]
+enum OwnedFsNode {
+    File {
+        name: String,
+        size: usize,
+    },
+    Directory {
+        name: String,
+        children: Vec<OwnedFsNode>,
+    },
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+impl OwnedFsNode {
+    /// Returns the sum of the size of all sub-nodes.
+    fn total_size(&self) -> usize {
         match self {
-            FsNode::File { .. } => panic!("cannot push child to a file"),
-            FsNode::Directory { children, .. } => children.push(child),
+            OwnedFsNode::File { size, .. } => *size,
+            OwnedFsNode::Directory { children, .. } => {
+                children.iter().map(OwnedFsNode::total_size).sum()
+            }
+        }
+    }
+}
+
+impl<'a, 'fs> From<&'a FsNode<'fs>> for OwnedFsNode {
+    fn from(node: &'a FsNode<'fs>) -> Self {
+        match node {
+            FsNode::File { name, size } => OwnedFsNode::File {
+                name: name.to_string(),
+                size: *size,
+            },
+            FsNode::Directory { name, children } => OwnedFsNode::Directory {
+                name: name.to_string(),
+                children: children
+                    .iter()
+                    .map(|child| OwnedFsNode::from(&*child.borrow()))
+                    .collect(),
+            },
         }
     }
 }
@@ -73,29 +204,38 @@ impl<'fs> FsNode<'fs> {
 // Use type alias instead of a new type for simplicity.
 type DirStack<'fs> = Vec<Rc<RefCell<FsNode<'fs>>>>;
 
-/// Returns a reference to the top node of the stack. Panics if the stack is empty.
-fn top<'a, 'fs>(stack: &'a DirStack<'fs>) -> Ref<'a, FsNode<'fs>> {
-    stack.last().expect("unexpected empty stack").borrow()
+/// Returns a reference to the top node of the stack, or an error if the stack is empty.
+fn top<'a, 'fs>(stack: &'a DirStack<'fs>) -> Result<Ref<'a, FsNode<'fs>>> {
+    Ok(stack
+        .last()
+        .ok_or_else(|| anyhow!("unexpected empty stack"))?
+        .borrow())
 }
 
-/// Pushes `node` in the top node's children list. Panics if the stack is empty.
-fn push_child_in_top_fs_node<'a, 'fs>(stack: &'a DirStack<'fs>, node: Rc<RefCell<FsNode<'fs>>>) {
+/// Pushes `node` in the top node's children list, or returns an error if the stack is empty.
+fn push_child_in_top_fs_node<'fs>(
+    stack: &DirStack<'fs>,
+    node: Rc<RefCell<FsNode<'fs>>>,
+) -> Result<()> {
     stack
         .last()
-        .expect("unexpected empty stack")
+        .ok_or_else(|| anyhow!("unexpected empty stack"))?
         .borrow_mut()
         .push_child(node)
 }
 
 /// Parses a shell session output log and infer the `Filesystem` structure from it.
-fn parse_shell_session_output<'fs>(s: &'fs str) -> Filesystem<'fs> {
+///
+/// A `cd ..` issued at the root of the filesystem is an error if `strict`, and leaves the current
+/// directory unchanged otherwise.
+fn parse_shell_session_output<'fs>(s: &'fs str, strict: bool) -> Result<Filesystem<'fs>> {
     let root = FsNode::directory("/", vec![]);
     let mut dir_stack: DirStack<'fs> = vec![];
 
     for line in s.lines() {
-        let mut iter = line.split(' ').into_iter();
+        let mut iter = line.split(' ');
         match iter.next() {
-            None => (), // Skip over blank lines.
+            None | Some("") => (), // Skip over blank lines.
             // A shell command. Only supporting `cd <ARG>` and `ls`.
             Some("$") => match iter.next() {
                 Some("ls") => continue, // Nothing to do here, the interesting part comes after.
@@ -109,28 +249,31 @@ fn parse_shell_session_output<'fs>(s: &'fs str) -> Filesystem<'fs> {
                             dir_stack.clear();
                             dir_stack.push(root.clone());
                         }
-                        Some("..") => {
-                            dir_stack
-                                .pop()
-                                .expect("`cd ..`: unexpected empty dir stack");
-                        }
+                        Some("..") => match dir_stack.len() {
+                            0 => return Err(anyhow!("`cd ..`: unexpected empty dir stack")),
+                            1 if strict => return Err(anyhow!("`cd ..`: already at the root")),
+                            1 => (), // Stay at the root.
+                            _ => {
+                                dir_stack.pop();
+                            }
+                        },
                         Some(dir_name) => {
                             // Locate the child directory in the current directory, and push it
-                            // on the stack, or panic if not found.
-                            let node = top(&dir_stack).get_child_by_name(dir_name);
+                            // on the stack, or fail if not found.
+                            let node = top(&dir_stack)?.get_child_by_name(dir_name)?;
                             dir_stack.push(node);
                         }
-                        None => panic!("missing argument to `cd` command"),
+                        None => return Err(anyhow!("missing argument to `cd` command")),
                     };
                 }
-                _ => panic!("unexpected shell command: `{:?}`", line),
+                _ => return Err(anyhow!("unexpected shell command: `{:?}`", line)),
             },
             // An entry in the output of the `ls` command.
             Some(ls_output) => {
                 // This line is part of the output of `ls`.
                 let rhs = iter
                     .next()
-                    .expect(&format!("unexpected `ls` output: `{:?}`", ls_output));
+                    .ok_or_else(|| anyhow!("unexpected `ls` output: `{:?}`", ls_output))?;
                 push_child_in_top_fs_node(
                     &dir_stack,
                     if ls_output == "dir" {
@@ -138,17 +281,17 @@ fn parse_shell_session_output<'fs>(s: &'fs str) -> Filesystem<'fs> {
                         FsNode::directory(rhs, vec![])
                     } else {
                         // This is a file declaration of the form `<SIZE> <NAME>`.
-                        let size = ls_output
-                            .parse()
-                            .expect(&format!("unexpected file size format: `{:?}`", ls_output));
+                        let size = ls_output.parse().map_err(|e| {
+                            anyhow!("unexpected file size format: `{:?}`: {:?}", ls_output, e)
+                        })?;
                         FsNode::file(rhs, size)
                     },
-                );
+                )?;
             }
         }
     }
 
-    Filesystem { root }
+    Ok(Filesystem { root })
 }
 
 /// An iterator yielding a flat list of `FsNode` in DFS order.
@@ -158,12 +301,11 @@ struct FsIterator<'fs> {
     current_child_index: usize,
 }
 
-impl<'fs> Iterator for FsIterator<'fs> {
-    type Item = Rc<RefCell<FsNode<'fs>>>;
-
+impl<'fs> FsIterator<'fs> {
+    /// Returns the next node along with its depth, where the children of the root are at depth 1.
     // NOTE: This is an imperative implementation of an otherwise recursive process.
     // TODO: Could we implement this iterator recursively?
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_with_depth(&mut self) -> Option<(usize, Rc<RefCell<FsNode<'fs>>>)> {
         let node = self.current_dir.borrow().clone();
         let mut children = match node {
             FsNode::File { .. } => panic!("internal error: expected dir, got file"),
@@ -183,6 +325,7 @@ impl<'fs> Iterator for FsIterator<'fs> {
             }
         }
         let child = children[self.current_child_index].clone();
+        let depth = self.dir_stack.len() + 1;
         self.current_child_index += 1;
         match *child.borrow() {
             FsNode::File { .. } => (),
@@ -193,7 +336,15 @@ impl<'fs> Iterator for FsIterator<'fs> {
                 self.current_child_index = 0;
             }
         }
-        Some(child)
+        Some((depth, child))
+    }
+}
+
+impl<'fs> Iterator for FsIterator<'fs> {
+    type Item = Rc<RefCell<FsNode<'fs>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_depth().map(|(_, node)| node)
     }
 }
 
@@ -211,38 +362,332 @@ impl<'fs> IntoIterator for &'fs Filesystem<'fs> {
     }
 }
 
-fn main() {
-    let input = include_str!("../../puzzles/day07.prod");
-    let fs = parse_shell_session_output(input);
-
-    let sum_size_dirs_below_100_000 = fs
-        .into_iter()
-        .filter_map(|node| {
-            let node = &*node.borrow();
-            match node {
-                FsNode::File { .. } => None,
-                FsNode::Directory { .. } => Some(node.get_total_size()),
-            }
-        })
-        .filter(|size| *size <= 100_000)
-        .sum::<usize>();
+impl<'fs> Filesystem<'fs> {
+    /// Merges the directories and files of `other` into this filesystem, matching them by path.
+    /// Files of `other` replace those at the same path.
+    fn merge(&mut self, other: &Filesystem<'fs>) {
+        self.root.borrow_mut().merge(&other.root.borrow());
+    }
 
-    println!("{:?}", sum_size_dirs_below_100_000);
+    /// Returns an `Iterator` yielding each node along with its depth in DFS order. The root itself
+    /// is not yielded, and its children are at depth 1.
+    fn iter_with_depth(&'fs self) -> impl Iterator<Item = (usize, Rc<RefCell<FsNode<'fs>>>)> {
+        let mut iter = self.into_iter();
+        iter::from_fn(move || iter.next_with_depth())
+    }
 
-    let size_smallest_dir_for_update = fs
-        .into_iter()
-        .filter_map(|node| {
-            let node = &*node.borrow();
-            match node {
+    /// Returns an `Iterator` yielding a deep copy of each node in DFS order. The root itself is not
+    /// yielded.
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn iter_owned(&'fs self) -> impl Iterator<Item = OwnedFsNode> + 'fs {
+        self.into_iter()
+            .map(|node| OwnedFsNode::from(&*node.borrow()))
+    }
+
+    /// Returns the total size of every directory below the root, in DFS order.
+    fn directory_sizes(&'fs self) -> Vec<usize> {
+        self.into_iter()
+            .filter_map(|node| {
+                let node = &*node.borrow();
+                match node {
+                    FsNode::File { .. } => None,
+                    FsNode::Directory { .. } => Some(node.get_total_size()),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the `(used, free)` space of a disk of `disk_size` holding the filesystem.
+    fn usage(&self, disk_size: usize) -> (usize, usize) {
+        let used = self.root.borrow().get_total_size();
+        (used, disk_size.saturating_sub(used))
+    }
+
+    /// Returns the total size of the smallest directory, root included, to delete so that a disk
+    /// of `disk_size` has at least `needed` free space, or `None` if deleting everything isn't
+    /// enough. Nothing needs deleting if there is already enough free space.
+    fn smallest_dir_to_free(&'fs self, disk_size: usize, needed: usize) -> Option<usize> {
+        let (used, free) = self.usage(disk_size);
+        let missing = needed.saturating_sub(free);
+        if missing == 0 {
+            return Some(0);
+        }
+        iter::once(used)
+            .chain(self.directory_sizes())
+            .filter(|size| *size >= missing)
+            .min()
+    }
+
+    /// Returns the total size of the largest directory, root included, whose total size is at most
+    /// `cap`, or `None` if every directory is larger. Same filter as part 1, but keeping the
+    /// largest size instead of summing them.
+    fn largest_dir_at_most(&'fs self, cap: usize) -> Option<usize> {
+        iter::once(self.root.borrow().get_total_size())
+            .chain(self.directory_sizes())
+            .filter(|size| *size <= cap)
+            .max()
+    }
+
+    /// Returns the `(depth, name, total size)` of every directory, root included, sorted by
+    /// decreasing size. Directories of the same size keep their DFS order.
+    fn disk_usage(&'fs self) -> Vec<(usize, &'fs str, usize)> {
+        let directories = self
+            .iter_with_depth()
+            .filter_map(|(depth, node)| match *node.borrow() {
                 FsNode::File { .. } => None,
-                FsNode::Directory { .. } => Some(node.get_total_size()),
+                FsNode::Directory { name, .. } => Some((depth, name)),
+            })
+            .zip(self.directory_sizes())
+            .map(|((depth, name), size)| (depth, name, size));
+        let mut usage = iter::once((0, "/", self.root.borrow().get_total_size()))
+            .chain(directories)
+            .collect::<Vec<_>>();
+        usage.sort_by(|(_, _, lhs), (_, _, rhs)| rhs.cmp(lhs));
+        usage
+    }
+
+    /// Returns the structural statistics of the filesystem, computed in a single traversal.
+    fn stats(&'fs self) -> FsStats<'fs> {
+        let mut stats = FsStats {
+            files: 0,
+            directories: 1,
+            max_depth: 0,
+            largest_file: None,
+        };
+        for (depth, node) in self.iter_with_depth() {
+            stats.max_depth = stats.max_depth.max(depth);
+            match *node.borrow() {
+                FsNode::File { name, size } => {
+                    stats.files += 1;
+                    if stats.largest_file.is_none_or(|(_, largest)| size > largest) {
+                        stats.largest_file = Some((name, size));
+                    }
+                }
+                FsNode::Directory { .. } => stats.directories += 1,
             }
-        })
-        .filter(|size| *size <= 70_000_000 - fs.root.borrow().get_total_size())
-        .max()
-        .expect("at least one value");
+        }
+        stats
+    }
+
+    /// Returns every node, root included, along with its absolute path (eg. `/a/e/i`) in DFS
+    /// order.
+    fn paths(&'fs self) -> Vec<(String, Rc<RefCell<FsNode<'fs>>>)> {
+        let mut ancestors: Vec<&str> = vec![];
+        let nodes = self.iter_with_depth().map(|(depth, node)| {
+            ancestors.truncate(depth - 1);
+            ancestors.push(node.borrow().name());
+            (format!("/{}", ancestors.join("/")), node)
+        });
+        iter::once(("/".to_owned(), Rc::clone(&self.root)))
+            .chain(nodes)
+            .collect()
+    }
+
+    /// Returns the path and total size of every directory, root included, whose total size is at
+    /// least `threshold`, sorted by decreasing size then by path.
+    fn dirs_at_least(&'fs self, threshold: usize) -> Vec<(String, usize)> {
+        let mut dirs = self
+            .paths()
+            .into_iter()
+            .filter_map(|(path, node)| {
+                let node = &*node.borrow();
+                match node {
+                    FsNode::File { .. } => None,
+                    FsNode::Directory { .. } => Some((path, node.get_total_size())),
+                }
+            })
+            .filter(|(_, size)| *size >= threshold)
+            .collect::<Vec<_>>();
+        dirs.sort_by(|(lhs_path, lhs_size), (rhs_path, rhs_size)| {
+            rhs_size.cmp(lhs_size).then_with(|| lhs_path.cmp(rhs_path))
+        });
+        dirs
+    }
+
+    /// Renders the filesystem as an indented tree, in the format used by the puzzle statement.
+    fn render_tree(&'fs self) -> String {
+        let mut out = String::new();
+        writeln!(out, "{}", self.root.borrow().label()).unwrap();
+        for (depth, node) in self.iter_with_depth() {
+            writeln!(out, "{}{}", "  ".repeat(depth), node.borrow().label()).unwrap();
+        }
+        out
+    }
+}
+
+/// The capacity of the disk of the device.
+const DISK_SIZE: usize = 70_000_000;
+
+/// The free space needed to run the update.
+const UPDATE_SIZE: usize = 30_000_000;
+
+/// Formats `bytes` using the largest unit among bytes, KiB and MiB that keeps the value above 1.
+fn human_size(bytes: usize) -> String {
+    const KIB: usize = 1024;
+    const MIB: usize = 1024 * KIB;
+
+    match bytes {
+        0..KIB => format!("{} B", bytes),
+        KIB..MIB => format!("{:.1} KiB", bytes as f64 / KIB as f64),
+        _ => format!("{:.1} MiB", bytes as f64 / MIB as f64),
+    }
+}
+
+/// Renders a `du`-like report of `usage`, one directory per line, indented by depth.
+fn render_disk_usage(usage: &[(usize, &str, usize)], bytes: bool) -> String {
+    let mut out = String::new();
+    for (depth, name, size) in usage {
+        let size = if bytes {
+            size.to_string()
+        } else {
+            human_size(*size)
+        };
+        writeln!(out, "{:>10}  {}{}", size, "  ".repeat(*depth), name).unwrap();
+    }
+    out
+}
+
+#[derive(Parser)]
+struct CmdlineArgs {
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    runner: RunnerArgs,
+
+    // Print the filesystem tree instead of solving the puzzle.
+    #[clap(long = "tree")]
+    tree: bool,
+
+    // Print the size of every directory, largest first, instead of solving the puzzle.
+    #[clap(long = "du")]
+    du: bool,
+
+    // Print sizes in bytes instead of human-readable units with `--du`.
+    #[clap(long = "bytes", requires = "du")]
+    bytes: bool,
+
+    // Print the number of files and directories, the maximum depth and the largest file instead
+    // of solving the puzzle.
+    #[clap(long = "stats")]
+    stats: bool,
+
+    // Print the directories of at least the given total size, largest first, instead of solving
+    // the puzzle.
+    #[clap(long = "min-size", value_name = "BYTES")]
+    min_size: Option<usize>,
+
+    // Print the total size of the largest directory of at most the given total size instead of
+    // solving the puzzle.
+    #[clap(long = "max-size", value_name = "BYTES")]
+    max_size: Option<usize>,
+
+    // Print the absolute path and total size of every file and directory instead of solving the
+    // puzzle.
+    #[clap(long = "paths")]
+    paths: bool,
+
+    // Merge the filesystem explored by the given shell session into the one of the puzzle input.
+    // Can be repeated, later sessions taking precedence.
+    #[clap(long = "merge", value_name = "PATH")]
+    merge: Vec<PathBuf>,
+
+    // Fail on `cd ..` at the root of the filesystem instead of ignoring it.
+    #[clap(long = "strict")]
+    strict: bool,
+}
+
+fn main() {
+    let cmdline_args = CmdlineArgs::parse();
+    let _log = cmdline_args.runner.init_logging();
+    let input = &cmdline_args
+        .input
+        .load(7)
+        .expect("failed to read puzzle input");
+    let mut fs = log::in_span("parse", || {
+        parse_shell_session_output(input, cmdline_args.strict)
+            .expect("failed to parse shell session")
+    });
+    let sessions = cmdline_args
+        .merge
+        .iter()
+        .map(|path| fs::read_to_string(path).expect("failed to read shell session"))
+        .collect::<Vec<_>>();
+    for session in &sessions {
+        let other = parse_shell_session_output(session, cmdline_args.strict)
+            .expect("failed to parse shell session");
+        fs.merge(&other);
+        log_debug!(lines = session.lines().count(), "merged shell session");
+    }
+
+    if cmdline_args.runner.print_parsed(|| fs.render_tree()) {
+        return;
+    }
+
+    if cmdline_args.tree {
+        for line in fs.render_tree().lines() {
+            let style = if line.ends_with("(dir)") {
+                Style::Directory
+            } else {
+                Style::File
+            };
+            println!("{}", viz::paint(line, style));
+        }
+        return;
+    }
+
+    if cmdline_args.stats {
+        let stats = fs.stats();
+        println!("Files: {}", stats.files);
+        println!("Directories: {}", stats.directories);
+        println!("Max depth: {}", stats.max_depth);
+        match stats.largest_file {
+            Some((name, size)) => println!("Largest file: {} ({})", name, size),
+            None => println!("Largest file: none"),
+        }
+        return;
+    }
+
+    if cmdline_args.paths {
+        for (path, node) in fs.paths() {
+            println!("{:>10}  {}", node.borrow().get_total_size(), path);
+        }
+        return;
+    }
+
+    if let Some(threshold) = cmdline_args.min_size {
+        for (path, size) in fs.dirs_at_least(threshold) {
+            println!("{:>10}  {}", size, path);
+        }
+        return;
+    }
+
+    if let Some(cap) = cmdline_args.max_size {
+        match fs.largest_dir_at_most(cap) {
+            Some(size) => println!("{}", size),
+            None => println!("No directory of at most {} bytes", cap),
+        }
+        return;
+    }
+
+    if cmdline_args.du {
+        print!("{}", render_disk_usage(&fs.disk_usage(), cmdline_args.bytes));
+        return;
+    }
 
-    println!("{:?}", size_smallest_dir_for_update);
+    runner::run(
+        &cmdline_args.runner,
+        || {
+            fs.directory_sizes()
+                .into_iter()
+                .filter(|size| *size <= 100_000)
+                .sum::<usize>()
+        },
+        || {
+            fs.smallest_dir_to_free(DISK_SIZE, UPDATE_SIZE)
+                .expect("not enough space for the update")
+        },
+    );
 }
 
 #[cfg(test)]
@@ -404,4 +849,287 @@ mod tests {
             .sum::<usize>();
         assert_eq!(sum_largest_dirs, 95437)
     }
+
+    #[test]
+    fn iter_with_depth_sample() {
+        let input = include_str!("../../puzzles/day07.test");
+        let fs = parse_shell_session_output(input, true).unwrap();
+        let depths = fs
+            .iter_with_depth()
+            .map(|(depth, node)| {
+                let name = match *node.borrow() {
+                    FsNode::File { name, .. } | FsNode::Directory { name, .. } => name,
+                };
+                (depth, name)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            depths,
+            vec![
+                (1, "a"),
+                (2, "e"),
+                (3, "i"),
+                (2, "f"),
+                (2, "g"),
+                (2, "h.lst"),
+                (1, "b.txt"),
+                (1, "c.dat"),
+                (1, "d"),
+                (2, "j"),
+                (2, "d.log"),
+                (2, "d.ext"),
+                (2, "k"),
+            ]
+        );
+    }
+
+    #[test]
+    fn iter_owned_sample() {
+        let input = include_str!("../../puzzles/day07.test");
+        let fs = parse_shell_session_output(input, true).unwrap();
+        let owned_sizes = fs
+            .iter_owned()
+            .map(|node| node.total_size())
+            .collect::<Vec<_>>();
+        let sizes = fs
+            .into_iter()
+            .map(|node| node.borrow().get_total_size())
+            .collect::<Vec<_>>();
+
+        assert_eq!(owned_sizes.len(), 13);
+        assert_eq!(owned_sizes, sizes);
+        assert_eq!(fs.iter_owned().next().unwrap().total_size(), 94853);
+
+        let root = OwnedFsNode::from(&*fs.root.borrow());
+        assert_eq!(root.total_size(), 48381165);
+        let json = serde_json::to_string(&root).unwrap();
+        assert_eq!(serde_json::from_str::<OwnedFsNode>(&json).unwrap(), root);
+    }
+
+    #[test]
+    fn stats_sample() {
+        let input = include_str!("../../puzzles/day07.test");
+        let fs = parse_shell_session_output(input, true).unwrap();
+
+        assert_eq!(
+            fs.stats(),
+            FsStats {
+                files: 10,
+                directories: 4,
+                max_depth: 3,
+                largest_file: Some(("b.txt", 14848514)),
+            }
+        );
+    }
+
+    #[test]
+    fn merge_partial_sessions() {
+        // The sample explored twice: first `/` and `/a` without `/a/e`, then `/a/e` and `/d`.
+        let first = "$ cd /\n$ ls\ndir a\n14848514 b.txt\n8504156 c.dat\ndir d\n$ cd a\n$ ls\n\
+                     dir e\n29116 f\n2557 g\n62596 h.lst\n";
+        let second = "$ cd /\n$ ls\ndir a\ndir d\n$ cd a\n$ ls\ndir e\n$ cd e\n$ ls\n584 i\n\
+                      $ cd /\n$ cd d\n$ ls\n4060174 j\n8033020 d.log\n5626152 d.ext\n7214296 k\n";
+        let mut fs = parse_shell_session_output(first, true).unwrap();
+        fs.merge(&parse_shell_session_output(second, true).unwrap());
+
+        let sample = parse_shell_session_output(include_str!("../../puzzles/day07.test"), true);
+        let sample = sample.unwrap();
+        assert_eq!(fs.root.borrow().get_total_size(), 48381165);
+        assert_eq!(fs.stats(), sample.stats());
+        assert_eq!(fs.render_tree(), sample.render_tree());
+    }
+
+    #[test]
+    fn merge_takes_latest_file_sizes() {
+        let input = include_str!("../../puzzles/day07.test");
+        let mut fs = parse_shell_session_output(input, true).unwrap();
+        let update = parse_shell_session_output("$ cd /\n$ ls\n1 b.txt\n", true).unwrap();
+        fs.merge(&update);
+        assert_eq!(fs.root.borrow().get_total_size(), 48381165 - 14848513);
+
+        // Merging copies the nodes, so that updating the merged filesystem leaves `update` as is.
+        let newer = parse_shell_session_output("$ cd /\n$ ls\n2 b.txt\n", true).unwrap();
+        fs.merge(&newer);
+        assert_eq!(fs.root.borrow().get_total_size(), 48381165 - 14848512);
+        assert_eq!(update.root.borrow().get_total_size(), 1);
+        assert_eq!(fs.stats().files, 10);
+    }
+
+    #[test]
+    fn usage_sample() {
+        let input = include_str!("../../puzzles/day07.test");
+        let fs = parse_shell_session_output(input, true).unwrap();
+
+        assert_eq!(fs.usage(DISK_SIZE), (48381165, 21618835));
+        assert_eq!(fs.usage(40_000_000), (48381165, 0));
+    }
+
+    #[test]
+    fn smallest_dir_to_free_sample() {
+        let input = include_str!("../../puzzles/day07.test");
+        let fs = parse_shell_session_output(input, true).unwrap();
+
+        assert_eq!(fs.smallest_dir_to_free(DISK_SIZE, UPDATE_SIZE), Some(24933642));
+        // Only deleting the root frees enough space.
+        assert_eq!(fs.smallest_dir_to_free(DISK_SIZE, 70_000_000), Some(48381165));
+        assert_eq!(fs.smallest_dir_to_free(DISK_SIZE, 20_000_000), Some(0));
+        assert_eq!(fs.smallest_dir_to_free(DISK_SIZE, 70_000_001), None);
+    }
+
+    #[test]
+    fn largest_dir_at_most_sample() {
+        let input = include_str!("../../puzzles/day07.test");
+        let fs = parse_shell_session_output(input, true).unwrap();
+
+        assert_eq!(fs.largest_dir_at_most(100_000), Some(94853));
+        assert_eq!(fs.largest_dir_at_most(94852), Some(584));
+        assert_eq!(fs.largest_dir_at_most(584), Some(584));
+        assert_eq!(fs.largest_dir_at_most(583), None);
+        // The root is a directory too.
+        assert_eq!(fs.largest_dir_at_most(usize::MAX), Some(48381165));
+    }
+
+    #[test]
+    fn stats_empty() {
+        let fs = parse_shell_session_output("$ cd /", true).unwrap();
+
+        assert_eq!(
+            fs.stats(),
+            FsStats {
+                files: 0,
+                directories: 1,
+                max_depth: 0,
+                largest_file: None,
+            }
+        );
+    }
+
+    #[test]
+    fn dirs_at_least_sample() {
+        let input = include_str!("../../puzzles/day07.test");
+        let fs = parse_shell_session_output(input, true).unwrap();
+
+        assert_eq!(
+            fs.dirs_at_least(90_000),
+            vec![
+                ("/".to_owned(), 48381165),
+                ("/d".to_owned(), 24933642),
+                ("/a".to_owned(), 94853),
+            ]
+        );
+        assert_eq!(fs.dirs_at_least(0).last(), Some(&("/a/e".to_owned(), 584)));
+        assert_eq!(fs.dirs_at_least(50_000_000), vec![]);
+    }
+
+    #[test]
+    fn paths_sample() {
+        let input = include_str!("../../puzzles/day07.test");
+        let fs = parse_shell_session_output(input, true).unwrap();
+        let paths = fs.paths();
+
+        assert_eq!(paths.len(), 14);
+        assert_eq!(paths[0].0, "/");
+        assert!(Rc::ptr_eq(&paths[0].1, &fs.root));
+
+        let (deepest_path, deepest_node) = paths
+            .iter()
+            .max_by_key(|(path, _)| path.matches('/').count())
+            .unwrap();
+        assert_eq!(deepest_path, "/a/e/i");
+        assert_eq!(
+            *deepest_node.borrow(),
+            FsNode::File {
+                name: "i",
+                size: 584
+            }
+        );
+        assert_eq!(
+            paths
+                .iter()
+                .map(|(path, _)| path.as_str())
+                .filter(|path| path.starts_with("/d/"))
+                .collect::<Vec<_>>(),
+            ["/d/j", "/d/d.log", "/d/d.ext", "/d/k"]
+        );
+    }
+
+    #[test]
+    fn dirs_at_least_ties_sort_by_path() {
+        let input = "$ cd /\n$ ls\ndir b\ndir a\n$ cd b\n$ ls\n10 x\n$ cd ..\n$ cd a\n$ ls\n10 y";
+        let fs = parse_shell_session_output(input, true).unwrap();
+
+        assert_eq!(
+            fs.dirs_at_least(10),
+            vec![
+                ("/".to_owned(), 20),
+                ("/a".to_owned(), 10),
+                ("/b".to_owned(), 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn disk_usage_sample() {
+        let input = include_str!("../../puzzles/day07.test");
+        let fs = parse_shell_session_output(input, true).unwrap();
+
+        assert_eq!(
+            fs.disk_usage(),
+            vec![
+                (0, "/", 48381165),
+                (1, "d", 24933642),
+                (1, "a", 94853),
+                (2, "e", 584)
+            ]
+        );
+        assert_eq!(
+            render_disk_usage(&fs.disk_usage(), false),
+            "  46.1 MiB  /\n  23.8 MiB    d\n  92.6 KiB    a\n     584 B      e\n"
+        );
+    }
+
+    #[test]
+    fn human_size_units() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1024), "1.0 KiB");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(1024 * 1024), "1.0 MiB");
+    }
+
+    #[test]
+    fn cd_parent_at_root() {
+        let input = "$ cd /\n$ cd ..\n$ ls\n12 a\ndir b\n$ cd b\n$ cd ..\n$ cd ..\n$ ls\n";
+
+        let fs = parse_shell_session_output(input, false).unwrap();
+        assert_eq!(fs.render_tree(), "- / (dir)\n  - a (file, size=12)\n  - b (dir)\n");
+
+        let err = parse_shell_session_output(input, true).err().unwrap();
+        assert_eq!(err.to_string(), "`cd ..`: already at the root");
+    }
+
+    #[test]
+    fn cd_parent_before_root() {
+        assert!(parse_shell_session_output("$ cd ..\n", false).is_err());
+    }
+
+    #[test]
+    fn render_tree_sample() {
+        let input = include_str!("../../puzzles/day07.test");
+        let fs = parse_shell_session_output(input, true).unwrap();
+
+        insta::assert_snapshot!(fs.render_tree());
+    }
+
+    #[cfg(feature = "gen")]
+    #[test]
+    fn generated_input_parses() {
+        use advent_of_code_2022::generate;
+
+        for seed in 0..10 {
+            let input = generate::generate(7, seed, 100).unwrap();
+            assert!(parse_shell_session_output(&input, true).is_ok());
+        }
+    }
 }
@@ -1,4 +1,17 @@
+extern crate advent_of_code_2022;
+extern crate anyhow;
+extern crate clap;
+
+use advent_of_code_2022::grid::{parse_grid_of_digits, Direction, Grid};
+use advent_of_code_2022::input::InputArgs;
+use advent_of_code_2022::runner::{self, RunnerArgs};
+use advent_of_code_2022::viz::{self, Style};
+use advent_of_code_2022::{log, log_debug};
+use anyhow::{anyhow, Result};
+use clap::Parser;
+
 /// A rectangular forest of trees. Each tree is represented by its height (a 0-9 integer value).
+#[derive(Debug, PartialEq, Clone)]
 struct Forest {
     trees: Vec<u8>,
     width: usize,
@@ -17,33 +30,167 @@ impl Forest {
         self.trees.len() / self.width
     }
 
+    /// Returns the forest mirrored along its diagonal, ie. where the tree at `(x, y)` moves to
+    /// `(y, x)`. Visibility and scenic scores don't depend on the orientation of the forest.
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn transpose(&self) -> Forest {
+        let height = self.height();
+        Forest {
+            trees: (0..self.len())
+                .map(|index| self.at(index / height, index % height))
+                .collect(),
+            width: height,
+        }
+    }
+
+    /// Whether `(x, y)` is on the edge of the forest. Every tree of a single row or column forest
+    /// is on the edge.
+    fn is_edge(&self, x: usize, y: usize) -> bool {
+        x == 0 || x + 1 >= self.width || y == 0 || y + 1 >= self.height()
+    }
+
+    /// Returns the number of trees on the edge of the forest, which are all visible. Every tree of
+    /// a single row or column forest is on the edge.
+    fn edge_tree_count(&self) -> usize {
+        let (width, height) = (self.width, self.height());
+        if width <= 1 || height <= 1 {
+            width * height
+        } else {
+            2 * (width + height) - 4
+        }
+    }
+
     fn is_tree_hidden(&self, index: usize) -> bool {
-        let (x, y) = (index / self.height(), index % self.width);
+        let (x, y) = (index % self.width, index / self.width);
         let value = self.at(x, y);
 
-        if x == 0 || x == self.width - 1 || y == 0 || y == self.height() - 1 {
+        if self.is_edge(x, y) {
             return false;
         }
 
-        (0..x).any(|row| self.at(row, y) >= value)
-            && (x + 1..self.width).any(|row| self.at(row, y) >= value)
-            && (0..y).any(|col| self.at(x, col) >= value)
-            && (y + 1..self.height()).any(|col| self.at(x, col) >= value)
+        (0..x).any(|x| self.at(x, y) >= value)
+            && (x + 1..self.width).any(|x| self.at(x, y) >= value)
+            && (0..y).any(|y| self.at(x, y) >= value)
+            && (y + 1..self.height()).any(|y| self.at(x, y) >= value)
+    }
+}
+
+impl Forest {
+    /// Returns whether the tree at `(x, y)` is visible from the north, south, east and west edges
+    /// of the forest, in that order.
+    fn visibility_from_edges(&self, x: usize, y: usize) -> [bool; 4] {
+        let value = self.at(x, y);
+
+        [
+            (0..y).all(|y| self.at(x, y) < value),
+            (y + 1..self.height()).all(|y| self.at(x, y) < value),
+            (x + 1..self.width).all(|x| self.at(x, y) < value),
+            (0..x).all(|x| self.at(x, y) < value),
+        ]
+    }
+
+    /// Returns the number of trees visible from the north, south, east and west edges of the
+    /// forest, in that order. A tree visible from several edges counts for each of them.
+    fn visible_counts_by_edge(&self) -> [usize; 4] {
+        let mut counts = [0; 4];
+        for index in 0..self.len() {
+            let (x, y) = (index % self.width, index / self.width);
+            for (count, visible) in counts.iter_mut().zip(self.visibility_from_edges(x, y)) {
+                *count += visible as usize;
+            }
+        }
+        counts
+    }
+
+    /// Returns whether each tree is visible from at least one edge of the forest, in row-major
+    /// order.
+    fn visible_mask(&self) -> Vec<bool> {
+        (0..self.len())
+            .map(|index| !self.is_tree_hidden(index))
+            .collect()
+    }
+
+    /// Returns the `(x, y)` coordinates of the trees hidden from every edge, in reading order.
+    fn hidden_trees(&self) -> Vec<(usize, usize)> {
+        self.visible_mask()
+            .iter()
+            .enumerate()
+            .filter(|(_, visible)| !**visible)
+            .map(|(index, _)| (index % self.width, index / self.width))
+            .collect()
+    }
+
+    /// Renders the forest with the height of visible trees, and `.` in place of hidden trees.
+    /// Heights above 9 are rendered as base 36 digits (`a` for 10), and heights above 35 as `#`.
+    fn render_visibility(&self) -> String {
+        (0..self.height())
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| match self.is_tree_hidden(y * self.width + x) {
+                        true => '.',
+                        false => char::from_digit(self.at(x, y) as u32, 36).unwrap_or('#'),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Builds a forest from a grid of tree heights.
+impl From<Grid<u8>> for Forest {
+    fn from(grid: Grid<u8>) -> Self {
+        Forest {
+            width: grid.width(),
+            trees: grid.into_cells(),
+        }
     }
 }
 
 fn parse_forest_map(input: &str) -> Forest {
-    Forest {
-        trees: input
-            .chars()
-            .filter(|c| *c != '\n')
-            .map(|c| {
-                assert!(c >= '0' && c <= '9');
-                c as u8 - '0' as u8
+    parse_grid_of_digits(input)
+        .map(Forest::from)
+        .expect("failed to parse forest map")
+}
+
+/// Parses a forest map where the heights on each line are separated by `sep`, which allows heights
+/// beyond 9. Blank lines are ignored.
+fn parse_forest_sep(input: &str, sep: char) -> Result<Forest> {
+    let mut trees = vec![];
+    let mut width = None;
+
+    for (line_index, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = line
+            .split(sep)
+            .map(|height| {
+                height.trim().parse::<u8>().map_err(|e| {
+                    anyhow!("line {}: invalid height {:?}: {}", line_index + 1, height, e)
+                })
             })
-            .collect(),
-        width: input.lines().take(1).next().unwrap().chars().count(),
+            .collect::<Result<Vec<_>>>()?;
+
+        match width {
+            None => width = Some(row.len()),
+            Some(width) if width != row.len() => {
+                return Err(anyhow!(
+                    "line {}: expected {} trees, got {}",
+                    line_index + 1,
+                    width,
+                    row.len()
+                ))
+            }
+            Some(_) => (),
+        }
+        trees.extend(row);
     }
+
+    Ok(Forest {
+        trees,
+        width: width.ok_or_else(|| anyhow!("empty forest map"))?,
+    })
 }
 
 fn viewing_distance<I, F>(range: I, predicate: F) -> Option<usize>
@@ -54,16 +201,17 @@ where
     range
         .enumerate()
         .find(|(_, i)| predicate(*i))
-        .and_then(|(d, _)| Some(d + 1))
+        .map(|(d, _)| d + 1)
 }
 
 impl Forest {
+    /// Kept as the reference implementation of the scenic score of `analyze`.
     fn scenic_score(&self, index: usize) -> usize {
         let (w, h) = (self.width, self.height());
-        let (x, y) = (index / h, index % w);
+        let (x, y) = (index % w, index / w);
         let value = self.at(x, y);
 
-        if x == 0 || x == w - 1 || y == 0 || y == h - 1 {
+        if self.is_edge(x, y) {
             return 0;
         }
 
@@ -72,19 +220,491 @@ impl Forest {
             * viewing_distance((0..y).rev(), |col| self.at(x, col) >= value).unwrap_or(y)
             * viewing_distance(y + 1..h, |col| self.at(x, col) >= value).unwrap_or(h - y - 1)
     }
+
+    /// Renders the scenic score of each tree relative to the highest one, from ` ` (0) to `@`
+    /// (the highest score).
+    fn scenic_heatmap(&self) -> String {
+        const RAMP: &[u8] = b" .:-=+*#%@";
+
+        let scores = (0..self.len())
+            .map(|index| self.scenic_score(index))
+            .collect::<Vec<_>>();
+        let max_score = scores.iter().copied().max().unwrap_or(0).max(1);
+        scores
+            .chunks(self.width)
+            .map(|row| {
+                row.iter()
+                    .map(|score| char::from(RAMP[score * (RAMP.len() - 1) / max_score]))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The north, south, east and west directions, as `(dx, dy)` steps.
+const DIRECTIONS: [(isize, isize); 4] = [(0, -1), (0, 1), (1, 0), (-1, 0)];
+
+/// Returns the `(dx, dy)` step towards `direction` on the map, where `Up` is the north, ie. towards
+/// the first row.
+#[cfg_attr(not(test), allow(dead_code))]
+fn map_step(direction: Direction) -> (isize, isize) {
+    let (dx, dy) = direction.delta();
+    (dx as isize, -dy as isize)
+}
+
+impl Forest {
+    /// Looks from the tree at `(x, y)` towards `direction`, and returns the viewing distance, and
+    /// whether the view is blocked by a tree at least as tall (ie. the tree is hidden from that
+    /// edge).
+    fn view(&self, x: usize, y: usize, (dx, dy): (isize, isize)) -> (usize, bool) {
+        let value = self.at(x, y);
+        let (mut x, mut y) = (x, y);
+        let mut distance = 0;
+
+        loop {
+            x = match x.checked_add_signed(dx).filter(|x| *x < self.width) {
+                Some(x) => x,
+                None => return (distance, false),
+            };
+            y = match y.checked_add_signed(dy).filter(|y| *y < self.height()) {
+                Some(y) => y,
+                None => return (distance, false),
+            };
+            distance += 1;
+            if self.at(x, y) >= value {
+                return (distance, true);
+            }
+        }
+    }
+
+    /// Returns the coordinates of the first tree at least as tall as the tree at `(x, y)` towards
+    /// `direction`, or `None` if the view reaches the edge. The viewing distance is the number of
+    /// steps to that tree, or to the edge.
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn first_blocker(&self, x: usize, y: usize, direction: Direction) -> Option<(usize, usize)> {
+        let (dx, dy) = map_step(direction);
+        let (distance, blocked) = self.view(x, y, (dx, dy));
+        let distance = distance as isize;
+        blocked
+            .then(|| (x.wrapping_add_signed(dx * distance), y.wrapping_add_signed(dy * distance)))
+    }
+
+    /// Returns the number of visible trees and the highest scenic score, looking once in each
+    /// direction from each interior tree for both. Trees on the edge are visible and have a scenic
+    /// score of 0, so they are counted without looking.
+    fn analyze(&self) -> (usize, usize) {
+        let mut visible_count = self.edge_tree_count();
+        let mut max_scenic_score = 0;
+
+        let interior = |len: usize| 1..len.saturating_sub(1);
+        for (x, y) in
+            interior(self.height()).flat_map(|y| interior(self.width).map(move |x| (x, y)))
+        {
+            let mut visible = false;
+            let mut scenic_score = 1;
+            for direction in DIRECTIONS {
+                let (distance, blocked) = self.view(x, y, direction);
+                visible |= !blocked;
+                scenic_score *= distance;
+            }
+            visible_count += visible as usize;
+            max_scenic_score = max_scenic_score.max(scenic_score);
+        }
+
+        (visible_count, max_scenic_score)
+    }
+}
+
+#[derive(Parser)]
+struct CmdlineArgs {
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    runner: RunnerArgs,
+
+    // Parse the map as heights separated by the given character, instead of single digits.
+    #[clap(long = "separator")]
+    separator: Option<char>,
+
+    // Print the visible trees instead of solving the puzzle.
+    #[clap(long = "render")]
+    render: bool,
+
+    // Print the number of trees visible from each edge instead of solving the puzzle.
+    #[clap(long = "edges")]
+    edges: bool,
+
+    // Print the scenic score of each tree as a heatmap instead of solving the puzzle.
+    #[clap(long = "heatmap")]
+    heatmap: bool,
+
+    // Print the coordinates of the hidden trees and their count instead of solving the puzzle.
+    #[clap(long = "invert")]
+    invert: bool,
 }
 
 fn main() {
-    let forest = parse_forest_map(include_str!("../../puzzles/day08.prod"));
-
-    let num_visible = (0..forest.len())
-        .filter(|index| !forest.is_tree_hidden(*index))
-        .count();
-    println!("{:?}", num_visible);
-
-    let highest_scenic_score = (0..forest.len())
-        .map(|index| forest.scenic_score(index))
-        .max()
-        .unwrap();
-    println!("{:?}", highest_scenic_score);
+    let cmdline_args = CmdlineArgs::parse();
+    let _log = cmdline_args.runner.init_logging();
+    let input = &cmdline_args
+        .input
+        .load(8)
+        .expect("failed to read puzzle input");
+    let forest = log::in_span("parse", || match cmdline_args.separator {
+        None => parse_forest_map(input),
+        Some(sep) => parse_forest_sep(input, sep).expect("failed to parse forest map"),
+    });
+    log_debug!(width = forest.width, height = forest.height(), "parsed forest");
+    if cmdline_args.runner.print_parsed(|| format!("{:?}", forest)) {
+        return;
+    }
+
+    if cmdline_args.render {
+        let rendering = forest.render_visibility();
+        println!(
+            "{}",
+            viz::paint_chars(&rendering, |c| match c {
+                '.' => Some(Style::Dim),
+                '\n' => None,
+                _ => Some(Style::Lit),
+            })
+        );
+        return;
+    }
+
+    if cmdline_args.edges {
+        let [north, south, east, west] = forest.visible_counts_by_edge();
+        println!("North: {}", north);
+        println!("South: {}", south);
+        println!("East: {}", east);
+        println!("West: {}", west);
+        return;
+    }
+
+    if cmdline_args.heatmap {
+        println!("{}", forest.scenic_heatmap());
+        return;
+    }
+
+    if cmdline_args.invert {
+        let hidden_trees = forest.hidden_trees();
+        for (x, y) in &hidden_trees {
+            println!("{},{}", x, y);
+        }
+        println!("Hidden: {}", hidden_trees.len());
+        return;
+    }
+
+    // `analyze` computes both parts in a single pass, but each part runs it so that the timings and
+    // `--challenge` cover the actual work.
+    runner::run(&cmdline_args.runner, || forest.analyze().0, || forest.analyze().1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_forest_map_sample() {
+        let forest = parse_forest_map(include_str!("../../puzzles/day08.test"));
+
+        #[rustfmt::skip]
+        let expected = Forest {
+            trees: vec![
+                3, 0, 3, 7, 3,
+                2, 5, 5, 1, 2,
+                6, 5, 3, 3, 2,
+                3, 3, 5, 4, 9,
+                3, 5, 3, 9, 0,
+            ],
+            width: 5,
+        };
+        assert_eq!(forest, expected);
+        assert_eq!(forest.clone(), forest);
+    }
+
+    #[test]
+    fn parse_forest_map_matches_chars() {
+        let input = include_str!("../../puzzles/day08.prod");
+        let chars = Forest {
+            trees: input
+                .chars()
+                .filter(|c| *c != '\n')
+                .map(|c| c as u8 - b'0')
+                .collect(),
+            width: input.lines().next().unwrap().chars().count(),
+        };
+
+        assert_eq!(parse_forest_map(input), chars);
+    }
+
+    #[test]
+    fn transpose_non_square() {
+        let forest = parse_forest_map("123\n456\n");
+        let transposed = forest.transpose();
+
+        assert_eq!(transposed, parse_forest_map("14\n25\n36\n"));
+        assert_eq!(transposed.transpose(), forest);
+    }
+
+    #[test]
+    fn analyze_is_invariant_under_transpose() {
+        let sample = parse_forest_map(include_str!("../../puzzles/day08.test"));
+        // The sample without its last column, so that rows and columns can't be mixed up.
+        let non_square = Forest {
+            trees: (0..sample.len())
+                .filter(|index| index % sample.width != sample.width - 1)
+                .map(|index| sample.trees[index])
+                .collect(),
+            width: sample.width - 1,
+        };
+
+        for forest in [sample, non_square] {
+            let transposed = forest.transpose();
+            assert_eq!(transposed.analyze(), forest.analyze());
+            assert_eq!(
+                transposed
+                    .visible_mask()
+                    .iter()
+                    .filter(|visible| **visible)
+                    .count(),
+                forest.analyze().0
+            );
+            let max_scenic_score = |forest: &Forest| {
+                (0..forest.len())
+                    .map(|index| forest.scenic_score(index))
+                    .max()
+            };
+            assert_eq!(max_scenic_score(&transposed), max_scenic_score(&forest));
+            assert_eq!(max_scenic_score(&forest), Some(forest.analyze().1));
+        }
+    }
+
+    #[test]
+    fn render_visibility_sample() {
+        let forest = parse_forest_map(include_str!("../../puzzles/day08.test"));
+
+        assert_eq!(forest.render_visibility(), "30373\n255.2\n65.32\n3.5.9\n35390");
+    }
+
+    #[test]
+    fn render_visibility_multi_digit_heights() {
+        let forest = parse_forest_sep("10,3,12\n0,255,7\n1,2,3", ',').unwrap();
+
+        assert_eq!(forest.render_visibility(), "a3c\n0#7\n123");
+    }
+
+    #[test]
+    fn hidden_trees_sample() {
+        let forest = parse_forest_map(include_str!("../../puzzles/day08.test"));
+        let hidden_trees = forest.hidden_trees();
+
+        assert_eq!(hidden_trees, [(3, 1), (2, 2), (1, 3), (3, 3)]);
+        assert_eq!(hidden_trees.len() + forest.analyze().0, forest.width * forest.height());
+    }
+
+    #[test]
+    fn scenic_heatmap_sample() {
+        let forest = parse_forest_map(include_str!("../../puzzles/day08.test"));
+
+        assert_eq!(
+            forest.scenic_heatmap(),
+            ["     ", " .=. ", " *.: ", " .@- ", "     "].join("\n")
+        );
+    }
+
+    #[test]
+    fn scenic_heatmap_all_edges() {
+        let forest = parse_forest_map("12\n34");
+
+        assert_eq!(forest.scenic_heatmap(), "  \n  ");
+    }
+
+    #[test]
+    fn visible_counts_by_edge_sample() {
+        let forest = parse_forest_map(include_str!("../../puzzles/day08.test"));
+
+        assert_eq!(forest.visible_counts_by_edge(), [10, 8, 11, 11]);
+
+        let union = (0..forest.len())
+            .filter(|index| {
+                let (x, y) = (index % forest.width, index / forest.width);
+                forest.visibility_from_edges(x, y).contains(&true)
+            })
+            .count();
+        let visible = (0..forest.len())
+            .filter(|index| !forest.is_tree_hidden(*index))
+            .count();
+        assert_eq!(union, 21);
+        assert_eq!(union, visible);
+    }
+
+    #[test]
+    fn first_blocker_sample() {
+        let forest = parse_forest_map(include_str!("../../puzzles/day08.test"));
+
+        // The middle 5 of the second row, with a scenic score of 4.
+        assert_eq!(forest.first_blocker(2, 1, Direction::Up), None);
+        assert_eq!(forest.first_blocker(2, 1, Direction::Left), Some((1, 1)));
+        assert_eq!(forest.first_blocker(2, 1, Direction::Right), None);
+        assert_eq!(forest.first_blocker(2, 1, Direction::Down), Some((2, 3)));
+
+        // The middle 5 of the fourth row, with a scenic score of 8.
+        assert_eq!(forest.first_blocker(2, 3, Direction::Up), Some((2, 1)));
+        assert_eq!(forest.first_blocker(2, 3, Direction::Left), None);
+        assert_eq!(forest.first_blocker(2, 3, Direction::Down), None);
+        assert_eq!(forest.first_blocker(2, 3, Direction::Right), Some((4, 3)));
+
+        // Trees on the edge see the edge right away.
+        assert_eq!(forest.first_blocker(0, 1, Direction::Left), None);
+        assert_eq!(forest.first_blocker(0, 1, Direction::Right), Some((1, 1)));
+    }
+
+    #[test]
+    fn first_blocker_matches_viewing_distance() {
+        let forest = parse_forest_map(include_str!("../../puzzles/day08.test"));
+
+        for (x, y) in (0..5).flat_map(|y| (0..5).map(move |x| (x, y))) {
+            let distances = Direction::ALL.map(|direction| {
+                let (distance, _) = forest.view(x, y, map_step(direction));
+                let steps = match forest.first_blocker(x, y, direction) {
+                    Some((bx, by)) => bx.abs_diff(x) + by.abs_diff(y),
+                    None => match direction {
+                        Direction::Up => y,
+                        Direction::Down => 4 - y,
+                        Direction::Left => x,
+                        Direction::Right => 4 - x,
+                    },
+                };
+                assert_eq!(distance, steps, "({}, {}) {:?}", x, y, direction);
+                distance
+            });
+            let score = distances.iter().product::<usize>();
+            assert_eq!(score, forest.scenic_score(y * 5 + x), "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn analyze_sample() {
+        let forest = parse_forest_map(include_str!("../../puzzles/day08.test"));
+
+        assert_eq!(forest.analyze(), (21, 8));
+    }
+
+    #[test]
+    fn analyze_matches_separate_computations() {
+        let forest = parse_forest_map(include_str!("../../puzzles/day08.prod"));
+        let visible_count = (0..forest.len())
+            .filter(|index| !forest.is_tree_hidden(*index))
+            .count();
+        let max_scenic_score = (0..forest.len())
+            .map(|index| forest.scenic_score(index))
+            .max()
+            .unwrap();
+
+        assert_eq!(forest.analyze(), (visible_count, max_scenic_score));
+    }
+
+    /// Counts the trees on the edge of `forest` by checking each of them.
+    fn edge_tree_count_by_scan(forest: &Forest) -> usize {
+        (0..forest.len())
+            .filter(|index| forest.is_edge(index % forest.width, index / forest.width))
+            .count()
+    }
+
+    #[test]
+    fn edge_tree_count_square() {
+        let forest = parse_forest_map(include_str!("../../puzzles/day08.test"));
+
+        assert_eq!(forest.edge_tree_count(), 16);
+        assert_eq!(forest.edge_tree_count(), edge_tree_count_by_scan(&forest));
+    }
+
+    #[test]
+    fn edge_tree_count_non_square() {
+        let forest = parse_forest_map("3037\n2551\n6533\n");
+        assert_eq!(forest.edge_tree_count(), 10);
+        assert_eq!(forest.edge_tree_count(), edge_tree_count_by_scan(&forest));
+        assert_eq!(forest.transpose().edge_tree_count(), 10);
+
+        let forest = parse_forest_map(include_str!("../../puzzles/day08.prod"));
+        assert_eq!(forest.edge_tree_count(), edge_tree_count_by_scan(&forest));
+    }
+
+    #[test]
+    fn edge_tree_count_degenerate() {
+        assert_eq!(parse_forest_map("5").edge_tree_count(), 1);
+        assert_eq!(parse_forest_map("30919").edge_tree_count(), 5);
+        assert_eq!(parse_forest_map("3\n0\n9\n1\n").edge_tree_count(), 4);
+        assert_eq!(parse_forest_map("30\n25\n").edge_tree_count(), 4);
+    }
+
+    fn assert_all_visible_with_no_score(forest: &Forest) {
+        assert!((0..forest.len()).all(|index| !forest.is_tree_hidden(index)));
+        assert!((0..forest.len()).all(|index| forest.scenic_score(index) == 0));
+        assert_eq!(forest.analyze(), (forest.len(), 0));
+    }
+
+    #[test]
+    fn single_column_forest() {
+        let forest = parse_forest_map("3\n0\n9\n1\n");
+
+        assert_eq!((forest.width, forest.height()), (1, 4));
+        assert_all_visible_with_no_score(&forest);
+    }
+
+    #[test]
+    fn single_row_forest() {
+        let forest = parse_forest_map("30919");
+
+        assert_eq!((forest.width, forest.height()), (5, 1));
+        assert_all_visible_with_no_score(&forest);
+    }
+
+    #[test]
+    fn single_tree_forest() {
+        let forest = parse_forest_map("5");
+
+        assert_all_visible_with_no_score(&forest);
+        assert_eq!(forest.render_visibility(), "5");
+    }
+
+    #[test]
+    fn parse_forest_sep_multi_digit() {
+        let forest = parse_forest_sep("10,3,12\n0,255,7\n", ',').unwrap();
+
+        assert_eq!(forest.trees, vec![10, 3, 12, 0, 255, 7]);
+        assert_eq!(forest.width, 3);
+    }
+
+    #[test]
+    fn parse_forest_sep_matches_packed_digits() {
+        let forest = parse_forest_sep("3 0 3\n2 5 5", ' ').unwrap();
+        let packed = parse_forest_map("303\n255");
+
+        assert_eq!(forest.trees, packed.trees);
+        assert_eq!(forest.width, packed.width);
+    }
+
+    #[test]
+    fn parse_forest_sep_errors() {
+        assert!(parse_forest_sep("1,2\n3", ',').is_err());
+        assert!(parse_forest_sep("1,x", ',').is_err());
+        assert!(parse_forest_sep("1,256", ',').is_err());
+        assert!(parse_forest_sep("", ',').is_err());
+    }
+
+    #[cfg(feature = "gen")]
+    #[test]
+    fn generated_input_parses() {
+        use advent_of_code_2022::generate;
+
+        for seed in 0..10 {
+            let input = generate::generate(8, seed, 20).unwrap();
+            let forest = parse_grid_of_digits(&input).map(Forest::from).unwrap();
+            assert_eq!(forest, parse_forest_map(&input));
+        }
+    }
 }
@@ -1,6 +1,17 @@
-extern crate itertools;
+extern crate advent_of_code_2022;
+extern crate anyhow;
+extern crate clap;
 
 use std::collections::HashSet;
+use std::iter::{self, repeat_n};
+
+use advent_of_code_2022::grid::{self, Direction};
+use advent_of_code_2022::input::InputArgs;
+use advent_of_code_2022::runner::{self, RunnerArgs};
+use advent_of_code_2022::viz;
+use advent_of_code_2022::{log, log_debug};
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
 
 type Coordinates = (i64, i64);
 
@@ -17,89 +28,447 @@ impl<const N: usize> Rope<N> {
         Self { knots: [origin; N] }
     }
 
-    /// Returns a mutable reference to the head knot.
-    fn head_mut<'a>(&'a mut self) -> &'a mut Coordinates {
-        &mut self.knots[0]
-    }
-
     /// Returns a copy of the tail knot.
     fn tail(&self) -> Coordinates {
         self.knots[N - 1]
     }
 
-    /// Adjusts the position of `self.knot[idx + 1]` if needed.
-    /// Returns `true` if the position was changed, `false` otherwise.
-    fn play_simulation_for_next_knot(&mut self, idx: usize) -> bool {
-        let head = self.knots[idx];
-        let tail = &mut self.knots[idx + 1];
+    /// Moves the position of the head knot, then adjusts the position of the following knots
+    /// accordingly. Returns the number of following knots that moved.
+    fn perform_move(&mut self, direction: Direction) -> usize {
+        move_knots(&mut self.knots, direction)
+    }
+}
 
-        let delta_x = head.0 - tail.0;
-        let delta_y = head.1 - tail.1;
+/// A rope whose number of knots is only known at runtime.
+struct DynRope {
+    knots: Vec<Coordinates>,
+}
 
-        *tail = match (delta_x, delta_y) {
-            (x, y) if x.abs() <= 1 && y.abs() <= 1 => return false,
-            (x, 2) if x.abs() <= 1 => (head.0, tail.1 + 1),
-            (x, -2) if x.abs() <= 1 => (head.0, tail.1 - 1),
-            (2, y) if y.abs() <= 1 => (tail.0 + 1, head.1),
-            (-2, y) if y.abs() <= 1 => (tail.0 - 1, head.1),
-            (x, y) if x.abs() == 2 && y.abs() == 2 => (tail.0 + x.signum(), tail.1 + y.signum()),
-            (_, _) => panic!("step too large"),
-        };
+impl DynRope {
+    /// Same as `Rope::new`, with `length` knots.
+    fn new(length: usize, origin: Coordinates) -> Self {
+        assert!(length > 1);
+        Self {
+            knots: vec![origin; length],
+        }
+    }
 
-        true
+    /// Returns a copy of the tail knot.
+    fn tail(&self) -> Coordinates {
+        self.knots[self.knots.len() - 1]
     }
 
-    /// Moves the position of the head knot, then adjusts the position of the following knots
-    /// accordingly.
-    fn perform_move(&mut self, direction: &str) {
-        match direction {
-            "L" => self.head_mut().0 -= 1,
-            "R" => self.head_mut().0 += 1,
-            "U" => self.head_mut().1 += 1,
-            "D" => self.head_mut().1 -= 1,
-            _ => panic!("invalid direction"),
-        };
-
-        // Run the simulation on other knots of the rope.
-        for i in 0..N - 1 {
-            if !self.play_simulation_for_next_knot(i) {
-                break;
-            }
-        }
+    /// Same as `Rope::perform_move`.
+    fn perform_move(&mut self, direction: Direction) -> usize {
+        move_knots(&mut self.knots, direction)
     }
 }
 
-/// Runs the simulation for a rope of size `N`.
-fn run_simulation<const N: usize>(input: &str) -> usize {
-    let origin = (0, 0);
-    let mut rope = Rope::<N>::new(origin);
-    let mut trail = HashSet::new();
+/// Adjusts the position of `knots[idx + 1]` if needed.
+/// Returns `true` if the position was changed, `false` otherwise.
+fn follow(knots: &mut [Coordinates], idx: usize) -> bool {
+    let head = knots[idx];
+    let tail = &mut knots[idx + 1];
+
+    let delta_x = head.0 - tail.0;
+    let delta_y = head.1 - tail.1;
+
+    *tail = match (delta_x, delta_y) {
+        _ if grid::chebyshev(head, *tail) <= 1 => return false,
+        (x, 2) if x.abs() <= 1 => (head.0, tail.1 + 1),
+        (x, -2) if x.abs() <= 1 => (head.0, tail.1 - 1),
+        (2, y) if y.abs() <= 1 => (tail.0 + 1, head.1),
+        (-2, y) if y.abs() <= 1 => (tail.0 - 1, head.1),
+        (x, y) if x.abs() == 2 && y.abs() == 2 => (tail.0 + x.signum(), tail.1 + y.signum()),
+        (_, _) => panic!("step too large"),
+    };
+
+    true
+}
+
+/// Moves the head knot, ie. `knots[0]`, then adjusts the position of the following knots
+/// accordingly. Returns the number of following knots that moved.
+fn move_knots(knots: &mut [Coordinates], direction: Direction) -> usize {
+    let (dx, dy) = direction.delta();
+    let head = &mut knots[0];
+    *head = (head.0 + dx, head.1 + dy);
 
+    // Run the simulation on other knots of the rope. A knot that doesn't move leaves the rest
+    // of the rope in place.
+    let mut moved = 0;
+    for i in 0..knots.len() - 1 {
+        if !follow(knots, i) {
+            break;
+        }
+        moved += 1;
+    }
+    moved
+}
+
+/// Parses the motions of the head of the rope, one `DIRECTION STEPS` per line, eg. `R 4`.
+fn parse_motions(input: &str) -> Result<Vec<(Direction, usize)>> {
     input
         .lines()
-        .for_each(|motion| match motion.split_once(' ') {
-            Some((direction, steps)) => {
-                let steps = steps
-                    .parse::<usize>()
-                    .expect(&format!("expected number, got `{:?}`", steps));
-
-                for _ in 0..steps {
-                    rope.perform_move(direction);
-                    trail.insert(rope.tail());
+        .enumerate()
+        .map(|(index, motion)| {
+            let (direction, steps) = motion
+                .split_once(' ')
+                .ok_or_else(|| anyhow!("expected `DIRECTION STEPS`"))
+                .and_then(|(direction, steps)| Ok((direction.parse()?, steps.parse()?)))
+                .with_context(|| format!("line {}: invalid motion {:?}", index + 1, motion))?;
+            Ok((direction, steps))
+        })
+        .collect()
+}
+
+/// Runs the simulation for a rope of size `N`, starting at `(0, 0)`.
+fn run_simulation<const N: usize>(motions: &[(Direction, usize)]) -> usize {
+    run_simulation_from::<N>(motions, (0, 0))
+}
+
+/// Runs the simulation for a rope of size `N`, with all knots starting at `origin`.
+fn run_simulation_from<const N: usize>(
+    motions: &[(Direction, usize)],
+    origin: Coordinates,
+) -> usize {
+    tail_trail::<N>(motions, origin).len()
+}
+
+/// Runs the simulation for a rope of size `N`, and returns the set of positions visited by the tail.
+fn tail_trail<const N: usize>(
+    motions: &[(Direction, usize)],
+    origin: Coordinates,
+) -> HashSet<Coordinates> {
+    iter_tail_positions::<N>(motions, origin).collect()
+}
+
+/// Parses `input` once, and runs the simulation for a rope of each of `lengths`, starting at
+/// `(0, 0)`. Returns the number of positions visited by the tail of each rope.
+fn run_simulation_multi(input: &str, lengths: &[usize]) -> Result<Vec<usize>> {
+    let motions = parse_motions(input)?;
+    if let Some(length) = lengths.iter().find(|length| **length < 2) {
+        return Err(anyhow!("a rope has at least 2 knots, got {}", length));
+    }
+
+    Ok(lengths
+        .iter()
+        .map(|&length| run_simulation_dyn(&motions, length))
+        .collect())
+}
+
+/// Same as `run_simulation`, for a rope of `length` knots.
+fn run_simulation_dyn(motions: &[(Direction, usize)], length: usize) -> usize {
+    iter::once((0, 0))
+        .chain(iter_dyn_tail_positions(motions, length))
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Same as `iter_tail_positions`, for a rope of `length` knots starting at `(0, 0)`.
+fn iter_dyn_tail_positions(
+    motions: &[(Direction, usize)],
+    length: usize,
+) -> impl Iterator<Item = Coordinates> + '_ {
+    let mut rope = DynRope::new(length, (0, 0));
+
+    motions
+        .iter()
+        .flat_map(|&(direction, steps)| repeat_n(direction, steps))
+        .map(move |direction| {
+            rope.perform_move(direction);
+            rope.tail()
+        })
+}
+
+/// Renders the positions of `trail` as `#` within their bounding box, up being the positive `y`.
+fn render_trail(trail: &HashSet<Coordinates>) -> String {
+    grid::render_points(trail, '#', '.')
+}
+
+/// Plays `motions` on a rope of size `N` starting at `origin`, and yields the position of the tail
+/// after each step.
+fn iter_tail_positions<const N: usize>(
+    motions: &[(Direction, usize)],
+    origin: Coordinates,
+) -> impl Iterator<Item = Coordinates> + '_ {
+    let mut rope = Rope::<N>::new(origin);
+
+    motions
+        .iter()
+        .flat_map(|&(direction, steps)| repeat_n(direction, steps))
+        .map(move |direction| {
+            rope.perform_move(direction);
+            rope.tail()
+        })
+}
+
+/// Runs the simulation for a rope of size `N` starting at `(0, 0)`, and returns the first step at
+/// which the tail moves back onto a position it occupied before, along with that position.
+/// Steps are numbered from 1, the starting position counting as visited at step 0.
+fn first_tail_revisit<const N: usize>(
+    motions: &[(Direction, usize)],
+) -> Option<(usize, Coordinates)> {
+    let origin = (0, 0);
+    let mut trail = HashSet::from([origin]);
+    let mut previous_tail = origin;
+
+    for (step, tail) in (1..).zip(iter_tail_positions::<N>(motions, origin)) {
+        // A tail that does not move is not revisiting its position.
+        if tail != previous_tail && !trail.insert(tail) {
+            return Some((step, tail));
+        }
+        previous_tail = tail;
+    }
+
+    None
+}
+
+#[derive(Parser)]
+struct CmdlineArgs {
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    runner: RunnerArgs,
+
+    // Print the first step at which the tail revisits a position instead of solving the puzzle.
+    #[clap(long = "first-revisit")]
+    first_revisit: bool,
+
+    // The number of knots of the rope in part 2.
+    #[clap(long = "length", default_value_t = 10, value_parser = clap::value_parser!(u64).range(2..))]
+    length: u64,
+
+    // Print the number of positions visited by the tail of a rope of each of the given lengths
+    // instead of solving the puzzle.
+    #[clap(long = "compare", value_name = "LENGTHS", value_delimiter = ',')]
+    compare: Vec<usize>,
+
+    // Print the trail of the tail instead of solving the puzzle.
+    #[clap(long = "trail")]
+    trail: bool,
+}
+
+fn main() {
+    let cmdline_args = CmdlineArgs::parse();
+    let _log = cmdline_args.runner.init_logging();
+    let input = &cmdline_args
+        .input
+        .load(9)
+        .expect("failed to read puzzle input");
+    if !cmdline_args.compare.is_empty() {
+        let counts =
+            run_simulation_multi(input, &cmdline_args.compare).expect("failed to simulate ropes");
+        for (length, count) in cmdline_args.compare.iter().zip(counts) {
+            println!("{} knots: {}", length, count);
+        }
+        return;
+    }
+
+    let motions = &log::in_span("parse", || parse_motions(input).expect("failed to parse motions"));
+    log_debug!(motions = motions.len(), "parsed motions");
+    if cmdline_args.runner.print_parsed(|| {
+        motions
+            .iter()
+            .map(|(direction, steps)| format!("{:?} {}\n", direction, steps))
+            .collect()
+    }) {
+        return;
+    }
+
+    if cmdline_args.trail {
+        let trails = [
+            (2, tail_trail::<2>(motions, (0, 0))),
+            (10, tail_trail::<10>(motions, (0, 0))),
+        ];
+        for (knots, trail) in trails {
+            println!("{} knots:", knots);
+            println!("{}", viz::paint_chars(&render_trail(&trail), viz::pixel_style));
+        }
+        return;
+    }
+
+    if cmdline_args.first_revisit {
+        let revisits = [
+            (2, first_tail_revisit::<2>(motions)),
+            (10, first_tail_revisit::<10>(motions)),
+        ];
+        for (knots, revisit) in revisits {
+            match revisit {
+                Some((step, position)) => {
+                    println!("{} knots: step {} at {:?}", knots, step, position)
                 }
+                None => println!("{} knots: never", knots),
             }
-            _ => panic!("unexpected motion: {:?}", motion),
-        });
+        }
+        return;
+    }
 
-    trail.len()
+    runner::run(
+        &cmdline_args.runner,
+        || run_simulation::<2>(motions),
+        || run_simulation_dyn(motions, cmdline_args.length as usize),
+    );
 }
 
-fn main() {
-    let input = include_str!("../../puzzles/day09.prod");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_simulation_samples() {
+        let p1 = parse_motions(include_str!("../../puzzles/day09-p1.test")).unwrap();
+        let p2 = parse_motions(include_str!("../../puzzles/day09-p2.test")).unwrap();
+
+        assert_eq!(run_simulation::<2>(&p1), 13);
+        assert_eq!(run_simulation::<10>(&p1), 1);
+        assert_eq!(run_simulation::<10>(&p2), 36);
+    }
 
-    let unique_tail_position = run_simulation::<2>(input);
-    println!("{:?}", unique_tail_position);
+    #[test]
+    fn run_simulation_multi_samples() {
+        let p1 = include_str!("../../puzzles/day09-p1.test");
+        let p2 = include_str!("../../puzzles/day09-p2.test");
 
-    let unique_tail_position = run_simulation::<10>(input);
-    println!("{:?}", unique_tail_position);
+        assert_eq!(run_simulation_multi(p1, &[2, 10]).unwrap(), [13, 1]);
+        assert_eq!(run_simulation_multi(p2, &[10, 2, 10]).unwrap(), [36, 88, 36]);
+        assert!(run_simulation_multi(p1, &[]).unwrap().is_empty());
+        assert_eq!(
+            run_simulation_multi(p1, &[2, 1]).unwrap_err().to_string(),
+            "a rope has at least 2 knots, got 1"
+        );
+        assert!(run_simulation_multi("R x", &[2]).is_err());
+    }
+
+    #[test]
+    fn dyn_rope_matches_rope() {
+        for input in [
+            include_str!("../../puzzles/day09-p1.test"),
+            include_str!("../../puzzles/day09-p2.test"),
+        ] {
+            let motions = parse_motions(input).unwrap();
+
+            assert!(
+                iter_dyn_tail_positions(&motions, 2).eq(iter_tail_positions::<2>(&motions, (0, 0)))
+            );
+            assert!(iter_dyn_tail_positions(&motions, 10)
+                .eq(iter_tail_positions::<10>(&motions, (0, 0))));
+            assert_eq!(run_simulation_dyn(&motions, 2), run_simulation::<2>(&motions));
+            assert_eq!(run_simulation_dyn(&motions, 10), run_simulation::<10>(&motions));
+        }
+    }
+
+    #[test]
+    fn dyn_rope_counts_moved_knots() {
+        let (mut rope, mut dyn_rope) = (Rope::<10>::new((0, 0)), DynRope::new(10, (0, 0)));
+        for direction in repeat_n(Direction::Up, 12).chain(repeat_n(Direction::Left, 3)) {
+            assert_eq!(dyn_rope.perform_move(direction), rope.perform_move(direction));
+            assert_eq!(dyn_rope.tail(), rope.tail());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn dyn_rope_single_knot() {
+        DynRope::new(1, (0, 0));
+    }
+
+    #[test]
+    fn run_simulation_is_translation_invariant() {
+        let motions = parse_motions(include_str!("../../puzzles/day09-p2.test")).unwrap();
+
+        for origin in [(0, 0), (11, 5), (-7, 3), (1_000, -1_000)] {
+            assert_eq!(run_simulation_from::<2>(&motions, origin), run_simulation::<2>(&motions));
+            assert_eq!(run_simulation_from::<10>(&motions, origin), run_simulation::<10>(&motions));
+        }
+    }
+
+    #[test]
+    fn render_trail_sample() {
+        let trail = tail_trail::<10>(
+            &parse_motions(include_str!("../../puzzles/day09-p2.test")).unwrap(),
+            (0, 0),
+        );
+
+        assert_eq!(
+            render_trail(&trail),
+            [
+                "#.....................",
+                "#.............###.....",
+                "#............#...#....",
+                ".#..........#.....#...",
+                "..#..........#.....#..",
+                "...#........#.......#.",
+                "....#......#.........#",
+                ".....#..............#.",
+                "......#............#..",
+                ".......#..........#...",
+                "........#........#....",
+                ".........########.....",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn first_tail_revisit_back_and_forth() {
+        assert_eq!(first_tail_revisit::<2>(&parse_motions("R 3\nL 3").unwrap()), Some((6, (1, 0))));
+    }
+
+    #[test]
+    fn first_tail_revisit_never() {
+        assert_eq!(first_tail_revisit::<2>(&parse_motions("R 4\nU 4").unwrap()), None);
+        assert_eq!(first_tail_revisit::<10>(&parse_motions("R 3\nL 3").unwrap()), None);
+    }
+
+    #[test]
+    fn parse_motions_sample() {
+        let motions = parse_motions(include_str!("../../puzzles/day09-p1.test")).unwrap();
+
+        assert_eq!(motions.len(), 8);
+        assert_eq!(motions[0], (Direction::Right, 4));
+        assert_eq!(motions[7], (Direction::Right, 2));
+    }
+
+    #[test]
+    fn parse_motions_errors() {
+        let err = parse_motions("R 4\nX 2").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: invalid motion \"X 2\"");
+        assert_eq!(
+            format!("{:#}", err),
+            "line 2: invalid motion \"X 2\": invalid direction: \"X\""
+        );
+
+        assert!(parse_motions("R").is_err());
+        assert!(parse_motions("R -1").is_err());
+    }
+
+    #[test]
+    fn perform_move_counts_moved_knots() {
+        let mut rope = Rope::<2>::new((0, 0));
+        let moved = (0..5)
+            .map(|_| rope.perform_move(Direction::Right))
+            .collect::<Vec<_>>();
+        // The tail follows once the head is 2 steps away.
+        assert_eq!(moved, [0, 1, 1, 1, 1]);
+
+        let mut rope = Rope::<10>::new((0, 0));
+        let moved = (0..12)
+            .map(|_| rope.perform_move(Direction::Up))
+            .collect::<Vec<_>>();
+        // Each step stretches the rope by one more knot, until every knot follows the head.
+        assert_eq!(moved, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 9, 9]);
+    }
+
+    #[cfg(feature = "gen")]
+    #[test]
+    fn generated_input_parses() {
+        use advent_of_code_2022::generate;
+
+        for seed in 0..10 {
+            let input = generate::generate(9, seed, 100).unwrap();
+            assert_eq!(parse_motions(&input).unwrap().len(), 100);
+        }
+    }
 }
@@ -1,49 +1,431 @@
-extern crate itertools;
+extern crate advent_of_code_2022;
+extern crate anyhow;
+extern crate clap;
+#[cfg(feature = "image")]
+extern crate image;
+#[cfg(test)]
+extern crate insta;
 
-use itertools::Itertools;
+use advent_of_code_2022::input::InputArgs;
+use advent_of_code_2022::runner::{self, RunnerArgs};
+use advent_of_code_2022::viz;
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use std::collections::HashSet;
+use std::fmt;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-/// Returns an iterator over the values of the `X` register for over time (ie. at each CPU cycle).
-fn eval_inst<'a>(input: &'a str) -> impl Iterator<Item = i64> + 'a {
+/// A CPU instruction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Instruction {
+    Noop,
+    Addx(i64),
+}
+
+impl Instruction {
+    /// The number of cycles the instruction takes to complete.
+    fn cycles(&self) -> usize {
+        match self {
+            Instruction::Noop => 1,
+            Instruction::Addx(_) => 2,
+        }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(' ') {
+            None if s == "noop" => Ok(Instruction::Noop),
+            Some(("addx", val)) => Ok(Instruction::Addx(val.parse()?)),
+            _ => Err(anyhow!("invalid instruction: {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Noop => write!(f, "noop"),
+            Instruction::Addx(val) => write!(f, "addx {}", val),
+        }
+    }
+}
+
+/// Returns an iterator over the instructions of the program.
+fn iter_instructions(input: &str) -> impl Iterator<Item = Instruction> + '_ {
+    input.lines().map(|line| {
+        line.parse()
+            .unwrap_or_else(|_| panic!("invalid input line: {:?}", line))
+    })
+}
+
+/// Returns an iterator over each CPU cycle (starting at 1), the instruction in flight during that
+/// cycle, and the value of the `X` register during that cycle.
+fn trace<'a>(input: &'a str) -> impl Iterator<Item = (usize, Instruction, i64)> + 'a {
     let mut reg_x: i64 = 1;
 
-    input
-        .lines()
-        .flat_map(move |line| match line.split_once(' ') {
-            None => vec![reg_x],
-            Some(("addx", val)) => {
-                let prev_reg_x = reg_x;
-                reg_x += val.parse::<i64>().unwrap();
-                vec![prev_reg_x, prev_reg_x]
+    iter_instructions(input)
+        .flat_map(move |inst| {
+            let during = reg_x;
+            if let Instruction::Addx(val) = inst {
+                reg_x += val;
             }
-            _ => panic!("invalid input line: {:?}", line),
+            iter::repeat_n((inst, during), inst.cycles())
         })
+        .zip(1..)
+        .map(|((inst, reg_x), cycle)| (cycle, inst, reg_x))
 }
 
-fn main() {
-    let input = include_str!("../../puzzles/day10.prod");
+/// Returns an iterator over the values of the `X` register for over time (ie. at each CPU cycle).
+fn eval_inst<'a>(input: &'a str) -> impl Iterator<Item = i64> + 'a {
+    trace(input).map(|(_, _, reg_x)| reg_x)
+}
+
+/// Returns the value of the `X` register during `cycle` (starting at 1), or `None` if the program
+/// ends before.
+fn x_at_cycle(input: &str, cycle: usize) -> Option<i64> {
+    eval_inst(input).nth(cycle.checked_sub(1)?)
+}
 
-    let sum_signal_strength_sample = (1i64..)
-        .zip(eval_inst(input))
-        .filter_map(|(cycle, reg_x)| match cycle % 40 == 20 {
-            false => None,
-            true => Some(reg_x * cycle),
+/// The number of pixels in a row of the CRT.
+const CRT_WIDTH: usize = 40;
+
+/// The number of rows of the CRT.
+const CRT_HEIGHT: usize = 6;
+
+/// Returns the `(row, col)` coordinates of the pixels lit by the program. The CRT draws one pixel
+/// per cycle, and lights it if the 3 pixels wide sprite centered on `X` covers it.
+fn lit_pixels(input: &str) -> HashSet<(usize, usize)> {
+    eval_inst(input)
+        .enumerate()
+        .map(|(cycle, reg_x)| (cycle / CRT_WIDTH, cycle % CRT_WIDTH, reg_x))
+        .filter(|&(_, col, reg_x)| (reg_x - 1..=reg_x + 1).contains(&(col as i64)))
+        .map(|(row, col, _)| (row, col))
+        .collect()
+}
+
+/// Renders the CRT screen, one `String` per row of 40 pixels, and returns it along with the number
+/// of cycles the program is missing to fill all the rows of the CRT.
+///
+/// The screen stops at the last cycle of the program, unless `pad` is set, in which case the pixels
+/// of the missing cycles are drawn off.
+fn render_crt(input: &str, pad: bool) -> (Vec<String>, usize) {
+    let lit = lit_pixels(input);
+    let cycles = eval_inst(input).count();
+    let missing_cycles = (CRT_WIDTH * CRT_HEIGHT).saturating_sub(cycles);
+    let drawn = if pad { cycles + missing_cycles } else { cycles };
+
+    let rows = (0..drawn)
+        .step_by(CRT_WIDTH)
+        .map(|row_start| {
+            (row_start..drawn.min(row_start + CRT_WIDTH))
+                .map(|cycle| match lit.contains(&(cycle / CRT_WIDTH, cycle % CRT_WIDTH)) {
+                    true => '#',
+                    false => '.',
+                })
+                .collect::<String>()
         })
-        .sum::<i64>();
-
-    println!("{:?}", sum_signal_strength_sample);
-
-    eval_inst(input).chunks(40).into_iter().for_each(|chunk| {
-        let display_line = (0i64..)
-            .zip(chunk)
-            .map(|(pos, reg_x)| {
-                if (reg_x - 1..=reg_x + 1).contains(&pos) {
-                    '#'
-                } else {
-                    '.'
+        .collect();
+    (rows, missing_cycles)
+}
+
+/// Labels each row of `screen` with the cycle drawing its first pixel, and follows it with a line
+/// marking with `^` the 3 pixels of the sprite during that cycle, labeled with its `X` position.
+/// The rows of `screen` are copied as-is. Rows drawn after the end of the program have no sprite.
+fn annotate_crt(input: &str, screen: &[String]) -> Vec<String> {
+    let reg_x = eval_inst(input).collect::<Vec<_>>();
+    screen
+        .iter()
+        .enumerate()
+        .flat_map(|(row, pixels)| {
+            let first_cycle = row * CRT_WIDTH;
+            let sprite = match reg_x.get(first_cycle) {
+                Some(&reg_x) => {
+                    let marks = (0..CRT_WIDTH as i64)
+                        .map(|col| match (reg_x - 1..=reg_x + 1).contains(&col) {
+                            true => '^',
+                            false => ' ',
+                        })
+                        .collect::<String>();
+                    format!("{:<10} {}", format!("X = {}", reg_x), marks.trim_end())
                 }
-            })
-            .collect::<String>();
+                None => "X = -".to_owned(),
+            };
+            [
+                format!("{:<10} {}", format!("cycle {}", first_cycle + 1), pixels),
+                sprite,
+            ]
+        })
+        .collect()
+}
 
-        println!("{}", display_line);
+/// Saves the CRT `screen` as a PNG image at `path`, drawing each lit pixel as a black square of
+/// `scale` pixels on a white background.
+#[cfg(feature = "image")]
+fn save_crt_png(screen: &[String], path: &Path, scale: u32) -> Result<()> {
+    let height = screen.len() as u32;
+    let width = screen.iter().map(|row| row.len()).max().unwrap_or(0) as u32;
+    let image = image::GrayImage::from_fn(width * scale, height * scale, |x, y| {
+        let row = screen[(y / scale) as usize].as_bytes();
+        match row.get((x / scale) as usize) {
+            Some(b'#') => image::Luma([0]),
+            _ => image::Luma([255]),
+        }
     });
+    image.save(path)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "image"))]
+fn save_crt_png(_screen: &[String], _path: &Path, _scale: u32) -> Result<()> {
+    Err(anyhow!("`--png` requires the `image` feature"))
+}
+
+#[derive(Parser)]
+struct CmdlineArgs {
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    runner: RunnerArgs,
+
+    // Print the cycle, instruction in flight and `X` register of every cycle instead of solving
+    // the puzzle.
+    #[clap(long = "trace")]
+    trace: bool,
+
+    // Print the `X` register during the given cycle instead of solving the puzzle.
+    #[clap(long = "at-cycle", value_name = "CYCLE")]
+    at_cycle: Option<usize>,
+
+    // Draw the pixels of the cycles missing to fill the CRT off, instead of stopping the screen at
+    // the end of the program.
+    #[clap(long = "pad")]
+    pad: bool,
+
+    // Print each row of the CRT labeled with the cycle drawing its first pixel, above the position
+    // of the sprite during that cycle, instead of solving the puzzle.
+    #[clap(long = "annotate")]
+    annotate: bool,
+
+    // Save the CRT as a PNG image at the given path instead of solving the puzzle.
+    #[clap(long = "png", value_name = "PATH")]
+    png: Option<PathBuf>,
+
+    // The size in pixels of each CRT pixel in the PNG image.
+    #[clap(long = "scale", default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..), requires = "png")]
+    scale: u32,
+}
+
+fn main() {
+    let cmdline_args = CmdlineArgs::parse();
+    let _log = cmdline_args.runner.init_logging();
+    let input = &cmdline_args
+        .input
+        .load(10)
+        .expect("failed to read puzzle input");
+    if cmdline_args.runner.print_parsed(|| {
+        iter_instructions(input)
+            .map(|inst| format!("{}\n", inst))
+            .collect()
+    }) {
+        return;
+    }
+
+    if cmdline_args.trace {
+        println!("cycle  instruction  X");
+        for (cycle, inst, reg_x) in trace(input) {
+            println!("{:>5}  {:<11}  {}", cycle, inst.to_string(), reg_x);
+        }
+        return;
+    }
+
+    if let Some(cycle) = cmdline_args.at_cycle {
+        match x_at_cycle(input, cycle) {
+            Some(reg_x) => println!("X = {} during cycle {}", reg_x, cycle),
+            None => println!("the program ends before cycle {}", cycle),
+        }
+        return;
+    }
+
+    let (screen, missing_cycles) = render_crt(input, cmdline_args.pad);
+    if missing_cycles > 0 {
+        eprintln!("warning: the program is {} cycles short of filling the CRT", missing_cycles);
+    }
+
+    if cmdline_args.annotate {
+        for line in annotate_crt(input, &screen) {
+            println!("{}", line);
+        }
+        return;
+    }
+
+    if let Some(path) = &cmdline_args.png {
+        save_crt_png(&screen, path, cmdline_args.scale).expect("failed to save the CRT");
+        return;
+    }
+
+    runner::run(
+        &cmdline_args.runner,
+        || {
+            (1i64..)
+                .zip(eval_inst(input))
+                .filter_map(|(cycle, reg_x)| match cycle % 40 == 20 {
+                    false => None,
+                    true => Some(reg_x * cycle),
+                })
+                .sum::<i64>()
+        },
+        || {
+            let (screen, _) = render_crt(input, cmdline_args.pad);
+            viz::paint_chars(&screen.join("\n"), viz::pixel_style)
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_small_program() {
+        let input = "noop\naddx 3\naddx -5\n";
+
+        assert_eq!(
+            trace(input).collect::<Vec<_>>(),
+            vec![
+                (1, Instruction::Noop, 1),
+                (2, Instruction::Addx(3), 1),
+                (3, Instruction::Addx(3), 1),
+                (4, Instruction::Addx(-5), 4),
+                (5, Instruction::Addx(-5), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn x_at_cycle_sample() {
+        let input = include_str!("../../puzzles/day10.test");
+
+        assert_eq!(x_at_cycle(input, 1), Some(1));
+        assert_eq!(x_at_cycle(input, 20), Some(21));
+        assert_eq!(x_at_cycle(input, 60), Some(19));
+        assert_eq!(x_at_cycle(input, 220), Some(18));
+        assert_eq!(x_at_cycle(input, 240), eval_inst(input).last());
+        assert_eq!(x_at_cycle(input, 241), None);
+        assert_eq!(x_at_cycle(input, 0), None);
+        assert_eq!(x_at_cycle("", 1), None);
+    }
+
+    #[test]
+    fn parse_instruction() {
+        assert_eq!("noop".parse::<Instruction>().unwrap(), Instruction::Noop);
+        assert_eq!("addx -11".parse::<Instruction>().unwrap(), Instruction::Addx(-11));
+        assert_eq!(Instruction::Addx(-11).to_string(), "addx -11");
+        assert!("addx".parse::<Instruction>().is_err());
+        assert!("noop 1".parse::<Instruction>().is_err());
+        assert!("mulx 2".parse::<Instruction>().is_err());
+    }
+
+    #[test]
+    fn lit_pixels_sample() {
+        let lit = lit_pixels(include_str!("../../puzzles/day10.test"));
+
+        assert_eq!(lit.len(), 124);
+        assert!(lit.contains(&(0, 0)) && lit.contains(&(0, 1)) && !lit.contains(&(0, 2)));
+        assert!(lit.iter().all(|&(row, col)| row < 6 && col < CRT_WIDTH));
+    }
+
+    #[test]
+    fn render_crt_sample() {
+        let input = include_str!("../../puzzles/day10.test");
+
+        insta::assert_snapshot!(render_crt(input, false).0.join("\n"));
+        assert_eq!(render_crt(input, true), render_crt(input, false));
+        assert_eq!(render_crt(input, false).1, 0);
+    }
+
+    #[test]
+    fn render_crt_short_program() {
+        // 41 cycles: a full row, then the first pixel of the second row.
+        let input = "addx 15\n".repeat(20) + "noop\n";
+
+        let (screen, missing_cycles) = render_crt(&input, false);
+        assert_eq!(missing_cycles, 199);
+        assert_eq!(screen.len(), 2);
+        // The sprite moves right by 15 pixels every 2 cycles, so it only covers the first 2.
+        assert_eq!(screen[0], format!("##{}", ".".repeat(38)));
+        assert_eq!(screen[1], ".");
+
+        let (padded, missing_cycles) = render_crt(&input, true);
+        assert_eq!(missing_cycles, 199);
+        assert_eq!(padded.len(), 6);
+        assert_eq!(padded[0], screen[0]);
+        assert_eq!(padded[1], ".".repeat(40));
+        assert!(padded[2..].iter().all(|row| *row == ".".repeat(40)));
+    }
+
+    #[test]
+    fn annotate_crt_sample() {
+        let input = include_str!("../../puzzles/day10.test");
+        let (screen, _) = render_crt(input, false);
+        let annotated = annotate_crt(input, &screen);
+
+        assert_eq!(annotated.len(), 2 * CRT_HEIGHT);
+        assert_eq!(annotated[0], "cycle 1    ##..##..##..##..##..##..##..##..##..##..");
+        assert_eq!(annotated[1], "X = 1      ^^^");
+        assert_eq!(annotated[2], format!("cycle 41   {}", screen[1]));
+        // The annotation leaves the pixels untouched.
+        for (row, pixels) in screen.iter().enumerate() {
+            assert!(annotated[2 * row].ends_with(pixels.as_str()));
+        }
+    }
+
+    #[test]
+    fn annotate_crt_padded_rows() {
+        let (screen, _) = render_crt("noop\naddx -3", true);
+        let annotated = annotate_crt("noop\naddx -3", &screen);
+
+        assert_eq!(annotated[1], "X = 1      ^^^");
+        assert_eq!(annotated[3], "X = -");
+    }
+
+    #[test]
+    fn render_crt_empty_program() {
+        assert_eq!(render_crt("", false), (vec![], 240));
+        assert_eq!(render_crt("", true), (vec![".".repeat(40); 6], 240));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn save_crt_png_dimensions() {
+        let (screen, _) = render_crt(include_str!("../../puzzles/day10.test"), false);
+        let path = std::env::temp_dir().join(format!("day10-crt-{}.png", std::process::id()));
+
+        save_crt_png(&screen, &path, 3).unwrap();
+        let image = image::open(&path).unwrap().to_luma8();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.dimensions(), (40 * 3, 6 * 3));
+        // The first row of the sample starts with `##..`.
+        assert_eq!(image.get_pixel(5, 2)[0], 0);
+        assert_eq!(image.get_pixel(6, 2)[0], 255);
+    }
+
+    #[cfg(feature = "gen")]
+    #[test]
+    fn generated_input_parses() {
+        use advent_of_code_2022::generate;
+
+        for seed in 0..10 {
+            let input = generate::generate(10, seed, 100).unwrap();
+            for line in input.lines() {
+                assert!(line.parse::<Instruction>().is_ok(), "{:?}", line);
+            }
+        }
+    }
 }
@@ -1,4 +1,16 @@
-#[derive(Clone)]
+extern crate advent_of_code_2022;
+extern crate anyhow;
+extern crate clap;
+
+use advent_of_code_2022::input::InputArgs;
+use advent_of_code_2022::math;
+use advent_of_code_2022::runner::{self, RunnerArgs};
+use advent_of_code_2022::{log, log_debug, log_trace};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq)]
 enum WorryValue {
     Old,
     Num(u64),
@@ -6,29 +18,61 @@ enum WorryValue {
 
 impl WorryValue {
     fn eval(&self, old: u64) -> u64 {
-        match self {
-            &WorryValue::Old => old,
-            &WorryValue::Num(value) => value,
+        match *self {
+            WorryValue::Old => old,
+            WorryValue::Num(value) => value,
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 enum WorryFn {
     Add(WorryValue),
     Mul(WorryValue),
 }
 
 impl WorryFn {
-    fn apply(&self, old: u64) -> u64 {
+    /// Returns the new worry level, or an error if it doesn't fit in a `u64`.
+    fn apply(&self, old: u64) -> Result<u64> {
         match self {
-            WorryFn::Add(value) => old + value.eval(old),
-            WorryFn::Mul(value) => old * value.eval(old),
+            WorryFn::Add(value) => old
+                .checked_add(value.eval(old))
+                .ok_or_else(|| anyhow!("worry level overflow: {} + {}", old, value.eval(old))),
+            WorryFn::Mul(value) => old
+                .checked_mul(value.eval(old))
+                .ok_or_else(|| anyhow!("worry level overflow: {} * {}", old, value.eval(old))),
         }
     }
 }
 
-#[derive(Clone)]
+impl FromStr for WorryFn {
+    type Err = anyhow::Error;
+
+    /// Parses the right-hand side of an operation, of the form `"old * 19"`, `"old + 6"` or
+    /// `"old * old"`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (operator, operand) = s
+            .strip_prefix("old ")
+            .and_then(|rest| rest.split_once(' '))
+            .ok_or_else(|| anyhow!("unparseable operation: {:?}", s))?;
+        let value = match operand {
+            "old" => WorryValue::Old,
+            _ => WorryValue::Num(
+                operand
+                    .parse()
+                    .with_context(|| format!("unparseable operand: {:?}", operand))?,
+            ),
+        };
+
+        match operator {
+            "+" => Ok(WorryFn::Add(value)),
+            "*" => Ok(WorryFn::Mul(value)),
+            _ => bail!("unknown operator: {:?}", operator),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 struct TestFn {
     divisible: u64,
     target_if_divisible: usize,
@@ -43,108 +87,773 @@ impl TestFn {
             target_if_not_divisible,
         }
     }
+
+    /// Parses the three lines describing the test of a monkey, of the form:
+    ///
+    /// ```text
+    /// Test: divisible by 23
+    ///   If true: throw to monkey 2
+    ///   If false: throw to monkey 3
+    /// ```
+    fn from_lines(test: &str, if_true: &str, if_false: &str) -> Result<Self> {
+        let divisible = field_of(test, "Test: divisible by")?;
+        let divisible = divisible
+            .parse()
+            .with_context(|| format!("unparseable divisor: {:?}", divisible))?;
+        if divisible == 0 {
+            bail!("divisor must not be zero");
+        }
+        let target_if_divisible = field_of(if_true, "If true: throw to monkey")?;
+        let target_if_divisible = target_if_divisible
+            .parse()
+            .with_context(|| format!("unparseable target: {:?}", target_if_divisible))?;
+        let target_if_not_divisible = field_of(if_false, "If false: throw to monkey")?;
+        let target_if_not_divisible = target_if_not_divisible
+            .parse()
+            .with_context(|| format!("unparseable target: {:?}", target_if_not_divisible))?;
+
+        Ok(TestFn::new(divisible, target_if_divisible, target_if_not_divisible))
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 struct Monkey {
     items: Vec<u64>,
     worry: WorryFn,
     test: TestFn,
 }
 
-fn main() {
-    let _input = include_str!("../../puzzles/day11.test");
+/// Returns what follows `prefix` on `line`, ignoring the indentation.
+fn field_of<'a>(line: &'a str, prefix: &str) -> Result<&'a str> {
+    line.trim()
+        .strip_prefix(prefix)
+        .map(str::trim)
+        .ok_or_else(|| anyhow!("missing '{}' line", prefix))
+}
 
-    let puzzle_input = [
-        Monkey {
-            items: vec![65, 58, 93, 57, 66],
-            worry: WorryFn::Mul(WorryValue::Num(7)),
-            test: TestFn::new(19, 6, 4),
-        },
-        Monkey {
-            items: vec![76, 97, 58, 72, 57, 92, 82],
-            worry: WorryFn::Add(WorryValue::Num(4)),
-            test: TestFn::new(3, 7, 5),
-        },
-        Monkey {
-            items: vec![90, 89, 96],
-            worry: WorryFn::Mul(WorryValue::Num(5)),
-            test: TestFn::new(13, 5, 1),
-        },
-        Monkey {
-            items: vec![72, 63, 72, 99],
-            worry: WorryFn::Mul(WorryValue::Old),
-            test: TestFn::new(17, 0, 4),
-        },
-        Monkey {
-            items: vec![65],
-            worry: WorryFn::Add(WorryValue::Num(1)),
-            test: TestFn::new(2, 6, 2),
-        },
-        Monkey {
-            items: vec![97, 71],
-            worry: WorryFn::Add(WorryValue::Num(8)),
-            test: TestFn::new(11, 7, 3),
-        },
-        Monkey {
-            items: vec![83, 68, 88, 55, 87, 67],
-            worry: WorryFn::Add(WorryValue::Num(2)),
-            test: TestFn::new(5, 2, 1),
-        },
-        Monkey {
-            items: vec![64, 81, 50, 96, 82, 53, 62, 92],
-            worry: WorryFn::Add(WorryValue::Num(5)),
-            test: TestFn::new(7, 3, 0),
-        },
-    ];
-    let mut inspect_count = [0; 8];
+/// Consumes the next line of `lines` and returns what follows `prefix` on it, ignoring the
+/// indentation.
+fn field<'a>(lines: &mut impl Iterator<Item = &'a str>, prefix: &str) -> Result<&'a str> {
+    field_of(lines.next().unwrap_or_default(), prefix)
+}
 
-    let mut monkeys = puzzle_input.clone();
-    for _ in 0..20 {
+/// Parses an operation of the form `"new = old * 19"`.
+fn parse_operation(operation: &str) -> Result<WorryFn> {
+    operation
+        .strip_prefix("new = ")
+        .ok_or_else(|| anyhow!("unparseable operation: {:?}", operation))?
+        .parse()
+}
+
+impl FromStr for Monkey {
+    type Err = anyhow::Error;
+
+    /// Parses a single monkey block of the form:
+    ///
+    /// ```text
+    /// Monkey 0:
+    ///   Starting items: 79, 98
+    ///   Operation: new = old * 19
+    ///   Test: divisible by 23
+    ///     If true: throw to monkey 2
+    ///     If false: throw to monkey 3
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let mut lines = s.lines().filter(|line| !line.trim().is_empty());
+
+        field(&mut lines, "Monkey ")?;
+        let items = field(&mut lines, "Starting items:")?;
+        let items = if items.is_empty() {
+            vec![]
+        } else {
+            items
+                .split(',')
+                .map(|item| {
+                    item.trim()
+                        .parse()
+                        .with_context(|| format!("unparseable item: {:?}", item.trim()))
+                })
+                .collect::<Result<_>>()?
+        };
+        let worry = parse_operation(field(&mut lines, "Operation:")?)?;
+        let mut next_line = || lines.next().unwrap_or_default();
+        let test = TestFn::from_lines(next_line(), next_line(), next_line())?;
+        if let Some(line) = lines.next() {
+            bail!("unexpected line: {:?}", line);
+        }
+
+        Ok(Monkey { items, worry, test })
+    }
+}
+
+/// Parses the blank-line separated monkey blocks of `input`, checking that every monkey throws to
+/// a monkey that exists.
+fn parse_monkeys(input: &str) -> Result<Vec<Monkey>> {
+    let monkeys = input
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .enumerate()
+        .map(|(index, block)| block.parse().with_context(|| format!("monkey #{}", index)))
+        .collect::<Result<Vec<Monkey>>>()?;
+
+    for (index, monkey) in monkeys.iter().enumerate() {
+        for target in [
+            monkey.test.target_if_divisible,
+            monkey.test.target_if_not_divisible,
+        ] {
+            if target >= monkeys.len() || target == index {
+                bail!("monkey #{} throws to invalid monkey {}", index, target);
+            }
+        }
+    }
+
+    Ok(monkeys)
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
+
+/// Returns the least common multiple of the divisors of all `monkeys`. Reducing worry levels
+/// modulo this value preserves the outcome of every monkey's test.
+fn common_multiple(monkeys: &[Monkey]) -> u64 {
+    monkeys
+        .iter()
+        .map(|monkey| monkey.test.divisible)
+        .fold(1, lcm)
+}
+
+/// How worry levels are kept manageable after each inspection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Relief {
+    /// Worry levels are divided by the given factor, as in part 1.
+    DivideBy(u64),
+    /// Worry levels are reduced modulo the given value, which preserves the outcome of every test
+    /// if it is a common multiple of the divisors, as in part 2.
+    Modulo(u64),
+}
+
+impl Relief {
+    fn apply(&self, worry: u64) -> u64 {
+        match *self {
+            Relief::DivideBy(factor) => worry / factor,
+            Relief::Modulo(modulus) => worry % modulus,
+        }
+    }
+}
+
+impl Monkey {
+    /// Inspects `item`, applying `relief` afterwards, and returns its new worry level and the
+    /// index of the monkey it is thrown to, or an error if the worry level overflows.
+    fn next_target(&self, item: u64, relief: &Relief) -> Result<(u64, usize)> {
+        let item = relief.apply(self.worry.apply(item)?);
+        let target_idx = if item.is_multiple_of(self.test.divisible) {
+            self.test.target_if_divisible
+        } else {
+            self.test.target_if_not_divisible
+        };
+        Ok((item, target_idx))
+    }
+}
+
+/// Returns the total number of items held by `monkeys`.
+fn item_count(monkeys: &[Monkey]) -> usize {
+    monkeys.iter().map(|monkey| monkey.items.len()).sum()
+}
+
+/// Plays `rounds` rounds applying `relief` after each inspection, and returns the number of items
+/// inspected by each monkey, or an error if a worry level overflows.
+///
+/// Items are only ever thrown between monkeys, so debug builds also check that their number is
+/// the same after every round.
+fn simulate(monkeys: &[Monkey], rounds: usize, relief: Relief) -> Result<Vec<u64>> {
+    let mut monkeys = monkeys.to_vec();
+    let mut inspect_count = vec![0; monkeys.len()];
+    let total_items = item_count(&monkeys);
+
+    for round in 1..=rounds {
         for idx in 0..monkeys.len() {
             let items: Vec<u64> = monkeys[idx].items.drain(..).collect();
-            let monkey = monkeys[idx].clone();
             for item in items {
                 inspect_count[idx] += 1;
-                let item = monkey.worry.apply(item) / 3;
-                let target_idx = if item % monkey.test.divisible == 0 {
-                    monkey.test.target_if_divisible
-                } else {
-                    monkey.test.target_if_not_divisible
-                };
+                let (item, target_idx) = monkeys[idx].next_target(item, &relief)?;
                 monkeys[target_idx].items.push(item);
             }
         }
+
+        log_trace!(round, "simulated round");
+        if cfg!(debug_assertions) && item_count(&monkeys) != total_items {
+            bail!("round {}: {} items instead of {}", round, item_count(&monkeys), total_items);
+        }
     }
 
-    inspect_count.sort();
-    let monkey_business_level: u64 = inspect_count.iter().rev().take(2).product();
+    log_debug!(rounds, ?inspect_count, "simulated rounds");
+    Ok(inspect_count)
+}
 
-    println!("{:?}", monkey_business_level);
+/// Plays `rounds` rounds like `simulate`, and returns the path of each item: the indexes of the
+/// monkeys that inspected it, in order. Items are numbered in the order of the starting items.
+fn item_paths(monkeys: &[Monkey], rounds: usize, relief: Relief) -> Result<Vec<Vec<usize>>> {
+    let mut held = vec![vec![]; monkeys.len()];
+    let mut item_id = 0;
+    for (idx, monkey) in monkeys.iter().enumerate() {
+        for &item in &monkey.items {
+            held[idx].push((item_id, item));
+            item_id += 1;
+        }
+    }
+    let mut history = vec![vec![]; item_id];
 
-    let mut monkeys = puzzle_input.clone();
-    let mut inspect_count = [0; 8];
-    let common_multiple: u64 = monkeys.iter().map(|monkey| monkey.test.divisible).product();
+    for _ in 0..rounds {
+        for (idx, monkey) in monkeys.iter().enumerate() {
+            for (item_id, item) in std::mem::take(&mut held[idx]) {
+                history[item_id].push(idx);
+                let (item, target_idx) = monkey.next_target(item, &relief)?;
+                held[target_idx].push((item_id, item));
+            }
+        }
+    }
 
-    for _ in 0..10_000 {
-        for idx in 0..monkeys.len() {
-            let items: Vec<u64> = monkeys[idx].items.drain(..).collect();
-            let monkey = monkeys[idx].clone();
-            for item in items {
-                inspect_count[idx] += 1;
-                let item = monkey.worry.apply(item) % common_multiple;
-                let target_idx = if item % monkey.test.divisible == 0 {
+    Ok(history)
+}
+
+/// Returns the number of items inspected by each of the `monkey_count` monkeys, ie. the number of
+/// times each monkey appears across the item paths of `history`.
+fn counts_from_history(history: &[Vec<usize>], monkey_count: usize) -> Vec<u64> {
+    let mut inspect_count = vec![0; monkey_count];
+    for &idx in history.iter().flatten() {
+        inspect_count[idx] += 1;
+    }
+    inspect_count
+}
+
+/// Replays the first `rounds` rounds without relief, keeping each worry level both modulo the
+/// product of the divisors and modulo `modulus`, and fails on the first test whose outcome differs
+/// between the two.
+///
+/// Reducing modulo the product is trivially correct, so this catches a `modulus` that doesn't
+/// preserve the outcome of every test.
+fn check_modulus(monkeys: &[Monkey], modulus: u64, rounds: usize) -> Result<()> {
+    let product = monkeys
+        .iter()
+        .try_fold(1u64, |product, monkey| product.checked_mul(monkey.test.divisible))
+        .ok_or_else(|| anyhow!("product of divisors overflows"))?;
+    let mut items = monkeys
+        .iter()
+        .map(|monkey| {
+            monkey
+                .items
+                .iter()
+                .map(|&item| (item % product, item % modulus))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    for round in 1..=rounds {
+        for (idx, monkey) in monkeys.iter().enumerate() {
+            for (reference, reduced) in std::mem::take(&mut items[idx]) {
+                let reference = monkey.worry.apply(reference)? % product;
+                let reduced = monkey.worry.apply(reduced)? % modulus;
+                let divisible = reference.is_multiple_of(monkey.test.divisible);
+                if divisible != reduced.is_multiple_of(monkey.test.divisible) {
+                    bail!(
+                        "round {}: monkey {} tests {} modulo {} differently than {} modulo {}",
+                        round,
+                        idx,
+                        reduced,
+                        modulus,
+                        reference,
+                        product
+                    );
+                }
+                let target_idx = if divisible {
                     monkey.test.target_if_divisible
                 } else {
                     monkey.test.target_if_not_divisible
                 };
-                monkeys[target_idx].items.push(item);
+                items[target_idx].push((reference, reduced));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the monkey business, ie. the product of the two largest inspection counts.
+fn monkey_business(inspect_count: &[u64]) -> u64 {
+    math::top_two_product(inspect_count)
+}
+
+/// Lists the inspection count of each monkey in monkey order, the way the puzzle statement does.
+fn render_counts(inspect_count: &[u64]) -> String {
+    inspect_count
+        .iter()
+        .enumerate()
+        .map(|(index, count)| format!("Monkey {} inspected items {} times.", index, count))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Parser)]
+struct CmdlineArgs {
+    #[clap(flatten)]
+    input: InputArgs,
+
+    #[clap(flatten)]
+    runner: RunnerArgs,
+
+    // Print each monkey's divisor and the modulus used for part 2 before solving the puzzle.
+    #[clap(long = "explain")]
+    explain: bool,
+
+    // The factor worry levels are divided by after each inspection in part 1.
+    #[clap(long = "relief", default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..))]
+    relief: u64,
+
+    // The number of rounds played in part 1.
+    #[clap(long = "rounds", default_value_t = 20)]
+    rounds: usize,
+
+    // Print the monkeys each item goes through in part 1 instead of solving the puzzle.
+    #[clap(long = "history")]
+    history: bool,
+
+    // Print the number of items each monkey inspects in part 1 instead of solving the puzzle.
+    #[clap(long = "counts")]
+    counts: bool,
+}
+
+fn main() {
+    let cmdline_args = CmdlineArgs::parse();
+    let _log = cmdline_args.runner.init_logging();
+    let input = cmdline_args
+        .input
+        .load(11)
+        .expect("failed to read puzzle input");
+    let puzzle_input =
+        log::in_span("parse", || parse_monkeys(&input).expect("failed to parse monkeys"));
+    log_debug!(monkeys = puzzle_input.len(), items = item_count(&puzzle_input), "parsed monkeys");
+    if cmdline_args
+        .runner
+        .print_parsed(|| format!("{:#?}", puzzle_input))
+    {
+        return;
+    }
+
+    let modulus = common_multiple(&puzzle_input);
+
+    if cmdline_args.explain {
+        for (index, monkey) in puzzle_input.iter().enumerate() {
+            println!("Monkey {}: divisible by {}", index, monkey.test.divisible);
+        }
+        let product = puzzle_input
+            .iter()
+            .map(|monkey| monkey.test.divisible)
+            .product::<u64>();
+        println!("Product of divisors: {}", product);
+        println!("Least common multiple: {}", modulus);
+        println!("Part 2 reduces worry levels modulo {}", modulus);
+    }
+
+    let relief = Relief::DivideBy(cmdline_args.relief);
+    if cmdline_args.history {
+        let history =
+            item_paths(&puzzle_input, cmdline_args.rounds, relief).expect("failed to simulate");
+        for (item_id, path) in history.iter().enumerate() {
+            let path = path.iter().map(usize::to_string).collect::<Vec<_>>();
+            println!("Item {}: {}", item_id, path.join(" -> "));
+        }
+        return;
+    }
+
+    if cmdline_args.counts {
+        let inspect_count =
+            simulate(&puzzle_input, cmdline_args.rounds, relief).expect("failed to simulate");
+        println!("{}", render_counts(&inspect_count));
+        return;
+    }
+
+    if cfg!(debug_assertions) {
+        check_modulus(&puzzle_input, modulus, 20)
+            .expect("the modulus changes the outcome of a test");
+        let history = item_paths(&puzzle_input, 20, relief).expect("failed to simulate");
+        assert_eq!(
+            counts_from_history(&history, puzzle_input.len()),
+            simulate(&puzzle_input, 20, relief).expect("failed to simulate"),
+            "the item paths don't add up to the inspection counts"
+        );
+    }
+
+    runner::run(
+        &cmdline_args.runner,
+        || {
+            monkey_business(
+                &simulate(&puzzle_input, cmdline_args.rounds, relief).expect("failed to simulate"),
+            )
+        },
+        || {
+            monkey_business(
+                &simulate(&puzzle_input, 10_000, Relief::Modulo(modulus))
+                    .expect("failed to simulate"),
+            )
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: &str = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3";
+
+    fn parse_error(block: &str) -> String {
+        format!("{:#}", block.parse::<Monkey>().unwrap_err())
+    }
+
+    #[test]
+    fn parse_monkey() {
+        let monkey: Monkey = BLOCK.parse().unwrap();
+        assert_eq!(
+            monkey,
+            Monkey {
+                items: vec![79, 98],
+                worry: WorryFn::Mul(WorryValue::Num(19)),
+                test: TestFn::new(23, 2, 3),
             }
+        );
+    }
+
+    #[test]
+    fn parse_monkey_squaring_without_items() {
+        let block = BLOCK
+            .replace("Starting items: 79, 98", "Starting items:")
+            .replace("old * 19", "old * old");
+        let monkey: Monkey = block.parse().unwrap();
+        assert!(monkey.items.is_empty());
+        assert_eq!(monkey.worry, WorryFn::Mul(WorryValue::Old));
+    }
+
+    #[test]
+    fn parse_monkey_missing_lines() {
+        assert_eq!(parse_error(""), "missing 'Monkey ' line");
+        for (line, prefix) in [
+            ("  Starting items: 79, 98\n", "Starting items:"),
+            ("  Operation: new = old * 19\n", "Operation:"),
+            ("  Test: divisible by 23\n", "Test: divisible by"),
+            ("    If true: throw to monkey 2\n", "If true: throw to monkey"),
+        ] {
+            let block = BLOCK.replace(line, "");
+            assert_eq!(parse_error(&block), format!("missing '{}' line", prefix));
         }
+        let block = BLOCK.replace("\n    If false: throw to monkey 3", "");
+        assert_eq!(parse_error(&block), "missing 'If false: throw to monkey' line");
+    }
+
+    #[test]
+    fn parse_monkey_unparseable_values() {
+        let block = BLOCK.replace("79, 98", "79, x");
+        assert!(parse_error(&block).starts_with("unparseable item: \"x\""));
+        let block = BLOCK.replace("old * 19", "19 * old");
+        assert!(parse_error(&block).starts_with("unparseable operation"));
+        let block = BLOCK.replace("old * 19", "old * y");
+        assert!(parse_error(&block).starts_with("unparseable operand: \"y\""));
+        let block = BLOCK.replace("old * 19", "old / 19");
+        assert_eq!(parse_error(&block), "unknown operator: \"/\"");
+        let block = BLOCK.replace("by 23", "by twenty-three");
+        assert!(parse_error(&block).starts_with("unparseable divisor: \"twenty-three\""));
+        let block = BLOCK.replace("by 23", "by 0");
+        assert_eq!(parse_error(&block), "divisor must not be zero");
+        let block = BLOCK.replace("monkey 2", "monkey -2");
+        assert!(parse_error(&block).starts_with("unparseable target: \"-2\""));
+        let block = format!("{}\n  Extra: line", BLOCK);
+        assert_eq!(parse_error(&block), "unexpected line: \"  Extra: line\"");
+    }
+
+    #[test]
+    fn parse_worry_fn() {
+        assert_eq!("old * old".parse::<WorryFn>().unwrap(), WorryFn::Mul(WorryValue::Old));
+        assert_eq!("old + old".parse::<WorryFn>().unwrap(), WorryFn::Add(WorryValue::Old));
+        assert_eq!("old * 19".parse::<WorryFn>().unwrap(), WorryFn::Mul(WorryValue::Num(19)));
+        assert_eq!("old + 6".parse::<WorryFn>().unwrap(), WorryFn::Add(WorryValue::Num(6)));
+    }
+
+    #[test]
+    fn parse_worry_fn_errors() {
+        let parse_error = |s: &str| format!("{:#}", s.parse::<WorryFn>().unwrap_err());
+
+        assert_eq!(parse_error("old - 6"), "unknown operator: \"-\"");
+        assert!(parse_error("old * six").starts_with("unparseable operand: \"six\""));
+        assert!(parse_error("old * -6").starts_with("unparseable operand: \"-6\""));
+        assert_eq!(parse_error("6 * old"), "unparseable operation: \"6 * old\"");
+        assert_eq!(parse_error("old"), "unparseable operation: \"old\"");
     }
 
-    inspect_count.sort();
-    let monkey_business_level: u64 = inspect_count.iter().rev().take(2).product();
+    #[test]
+    fn test_fn_from_lines() {
+        let test = TestFn::from_lines(
+            "Test: divisible by 23",
+            "  If true: throw to monkey 2",
+            "  If false: throw to monkey 3",
+        );
+        assert_eq!(test.unwrap(), TestFn::new(23, 2, 3));
+
+        let err = TestFn::from_lines(
+            "Test: divisible by 23",
+            "If false: throw to monkey 3",
+            "If true: throw to monkey 2",
+        );
+        assert_eq!(err.unwrap_err().to_string(), "missing 'If true: throw to monkey' line");
+        let err = TestFn::from_lines(
+            "Test: divisible by x",
+            "If true: throw to monkey 2",
+            "If false: throw to monkey 3",
+        );
+        assert_eq!(err.unwrap_err().to_string(), "unparseable divisor: \"x\"");
+    }
+
+    #[test]
+    fn parse_sample_monkeys() {
+        let monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+        assert_eq!(monkeys.len(), 4);
+        assert_eq!(monkeys[3].items, vec![74]);
+        assert_eq!(monkeys[3].worry, WorryFn::Add(WorryValue::Num(3)));
+    }
+
+    #[test]
+    fn parse_monkeys_invalid_target() {
+        let err = parse_monkeys(BLOCK).unwrap_err();
+        assert_eq!(err.to_string(), "monkey #0 throws to invalid monkey 2");
+    }
+
+    #[test]
+    fn lcm_of_divisors() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(1, 7), 7);
+
+        let monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+        assert_eq!(common_multiple(&monkeys), 23 * 19 * 13 * 17);
+    }
+
+    #[test]
+    fn lcm_and_product_moduli_agree() {
+        let mut monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+        // Share a factor between divisors so that the LCM is smaller than the product.
+        monkeys[0].test.divisible = 13 * 2;
+        monkeys[1].test.divisible = 13 * 3;
+        let product = monkeys
+            .iter()
+            .map(|monkey| monkey.test.divisible)
+            .product::<u64>();
+        let lcm = common_multiple(&monkeys);
+        assert!(lcm < product);
+
+        assert_eq!(
+            simulate(&monkeys, 1_000, Relief::Modulo(lcm)).unwrap(),
+            simulate(&monkeys, 1_000, Relief::Modulo(product)).unwrap()
+        );
+    }
 
-    println!("{:?}", monkey_business_level);
+    #[test]
+    fn check_modulus_sample() {
+        let monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+        let lcm = common_multiple(&monkeys);
+
+        assert!(check_modulus(&monkeys, lcm, 20).is_ok());
+        assert!(check_modulus(&monkeys, 3 * lcm, 20).is_ok());
+        // Dropping a divisor from the modulus eventually changes the outcome of its test.
+        let err = check_modulus(&monkeys, lcm / 23, 20).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "round 3: monkey 0 tests 3496 modulo 4199 differently than 28690 modulo 96577"
+        );
+    }
+
+    #[test]
+    fn next_target_divisible() {
+        let monkey = Monkey {
+            items: vec![],
+            worry: WorryFn::Mul(WorryValue::Num(19)),
+            test: TestFn::new(23, 2, 3),
+        };
+
+        // 46 * 19 = 874 = 23 * 38.
+        assert_eq!(monkey.next_target(46, &Relief::Modulo(23 * 19)).unwrap(), (0, 2));
+        assert_eq!(monkey.next_target(46, &Relief::Modulo(1_000)).unwrap(), (874, 2));
+        // 874 / 2 = 437 = 23 * 19.
+        assert_eq!(monkey.next_target(46, &Relief::DivideBy(2)).unwrap(), (437, 2));
+    }
+
+    #[test]
+    fn next_target_not_divisible() {
+        let monkey = Monkey {
+            items: vec![],
+            worry: WorryFn::Mul(WorryValue::Num(19)),
+            test: TestFn::new(23, 2, 3),
+        };
+
+        // 79 * 19 = 1501, and 1501 / 3 = 500.
+        assert_eq!(monkey.next_target(79, &Relief::DivideBy(3)).unwrap(), (500, 3));
+        assert_eq!(monkey.next_target(79, &Relief::Modulo(1_000)).unwrap(), (501, 3));
+        // Dividing by 3 makes 874 not divisible anymore: 874 / 3 = 291.
+        assert_eq!(monkey.next_target(46, &Relief::DivideBy(3)).unwrap(), (291, 3));
+    }
+
+    #[test]
+    fn next_target_overflow() {
+        let monkey = Monkey {
+            items: vec![],
+            worry: WorryFn::Mul(WorryValue::Old),
+            test: TestFn::new(2, 0, 1),
+        };
+
+        assert!(monkey.next_target(u64::MAX, &Relief::DivideBy(3)).is_err());
+    }
+
+    #[test]
+    fn sample_monkey_business() {
+        let monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+        let modulus = common_multiple(&monkeys);
+
+        assert_eq!(monkey_business(&simulate(&monkeys, 20, Relief::DivideBy(3)).unwrap()), 10605);
+        assert_eq!(
+            monkey_business(&simulate(&monkeys, 10_000, Relief::Modulo(modulus)).unwrap()),
+            2713310158
+        );
+    }
+
+    #[test]
+    fn counts_from_sample_history() {
+        let monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+        let modulus = common_multiple(&monkeys);
+
+        let history = item_paths(&monkeys, 1, Relief::DivideBy(3)).unwrap();
+        assert_eq!(history.len(), 10);
+        // 79 goes from monkey 0 to 3, then 3 to 1, where it waits for the next round.
+        assert_eq!(history[0], [0, 3]);
+        assert_eq!(counts_from_history(&history, 4), [2, 4, 3, 5]);
+
+        for (rounds, relief) in [(20, Relief::DivideBy(3)), (1_000, Relief::Modulo(modulus))] {
+            let history = item_paths(&monkeys, rounds, relief).unwrap();
+            assert_eq!(
+                counts_from_history(&history, monkeys.len()),
+                simulate(&monkeys, rounds, relief).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn counts_from_empty_history() {
+        assert_eq!(counts_from_history(&[], 3), [0, 0, 0]);
+        assert_eq!(counts_from_history(&[vec![], vec![2, 2]], 3), [0, 0, 2]);
+    }
+
+    #[test]
+    fn sample_item_count_is_conserved() {
+        let monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+        assert_eq!(item_count(&monkeys), 10);
+
+        // `simulate` fails on the first round that changes the number of items.
+        let modulus = common_multiple(&monkeys);
+        assert!(simulate(&monkeys, 1_000, Relief::Modulo(modulus)).is_ok());
+        assert!(simulate(&monkeys, 20, Relief::DivideBy(3)).is_ok());
+    }
+
+    #[test]
+    fn render_sample_counts() {
+        let monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+        let inspect_count = simulate(&monkeys, 20, Relief::DivideBy(3)).unwrap();
+
+        assert_eq!(
+            render_counts(&inspect_count),
+            "Monkey 0 inspected items 101 times.
+Monkey 1 inspected items 95 times.
+Monkey 2 inspected items 7 times.
+Monkey 3 inspected items 105 times."
+        );
+        assert_eq!(monkey_business(&inspect_count), 101 * 105);
+    }
+
+    #[test]
+    fn sample_relief_sweep() {
+        let monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+
+        assert_eq!(simulate(&monkeys, 20, Relief::DivideBy(3)).unwrap(), vec![101, 95, 7, 105]);
+        assert_eq!(simulate(&monkeys, 20, Relief::DivideBy(4)).unwrap(), vec![96, 100, 5, 100]);
+        assert_eq!(simulate(&monkeys, 20, Relief::DivideBy(5)).unwrap(), vec![96, 100, 4, 100]);
+        assert_eq!(simulate(&monkeys, 20, Relief::DivideBy(7)).unwrap(), vec![32, 164, 157, 27]);
+    }
+
+    #[test]
+    fn no_relief_matches_modulo_for_few_rounds() {
+        let monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+        let modulus = common_multiple(&monkeys);
+
+        assert_eq!(
+            simulate(&monkeys, 3, Relief::DivideBy(1)).unwrap(),
+            simulate(&monkeys, 3, Relief::Modulo(modulus)).unwrap()
+        );
+    }
+
+    #[test]
+    fn worry_overflow_is_detected() {
+        let square = WorryFn::Mul(WorryValue::Old);
+        assert_eq!(square.apply(1 << 31).unwrap(), 1 << 62);
+        let err = square.apply(1 << 32).unwrap_err();
+        assert_eq!(err.to_string(), "worry level overflow: 4294967296 * 4294967296");
+
+        let add = WorryFn::Add(WorryValue::Num(1));
+        assert!(add.apply(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn simulate_reports_overflow() {
+        let monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+
+        assert!(simulate(&monkeys, 20, Relief::DivideBy(2)).is_err());
+    }
+
+    #[cfg(feature = "gen")]
+    #[test]
+    fn generated_input_parses() {
+        use advent_of_code_2022::generate;
+
+        for seed in 0..10 {
+            let input = generate::generate(11, seed, 8).unwrap();
+            let monkeys = parse_monkeys(&input).unwrap();
+            let modulus = common_multiple(&monkeys);
+            assert!(simulate(&monkeys, 1_000, Relief::Modulo(modulus)).is_ok());
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn logging_keeps_answers() {
+        use advent_of_code_2022::tracing::Level;
+
+        let monkeys = parse_monkeys(include_str!("../../puzzles/day11.test")).unwrap();
+        let modulus = common_multiple(&monkeys);
+        let solve = || {
+            (
+                simulate(&monkeys, 20, Relief::DivideBy(3)).unwrap(),
+                simulate(&monkeys, 1_000, Relief::Modulo(modulus)).unwrap(),
+            )
+        };
+
+        assert_eq!(log::with_discarded_logs(Level::TRACE, solve), solve());
+        assert_eq!(log::with_discarded_logs(Level::DEBUG, solve), solve());
+    }
 }
@@ -0,0 +1,56 @@
+//! Downloads puzzle inputs from adventofcode.com.
+
+#[cfg(feature = "download")]
+use anyhow::{Context, Result};
+#[cfg(feature = "download")]
+use std::fs;
+#[cfg(feature = "download")]
+use std::path::Path;
+
+/// The environment variable holding the `session` cookie of a logged-in Advent of Code account.
+pub const SESSION_ENV_VAR: &str = "AOC_SESSION";
+
+/// Identifies the tool to the Advent of Code servers, as requested by their maintainer.
+#[cfg(feature = "download")]
+const USER_AGENT: &str = "github.com/0xcharly/advent-of-code by charly@delay.gg";
+
+/// Returns the URL of the puzzle input for the given year and day.
+pub fn input_url(year: u32, day: u32) -> String {
+    format!("https://adventofcode.com/{}/day/{}/input", year, day)
+}
+
+/// Downloads the puzzle input for the given year and day to `path`.
+///
+/// Inputs never change once published, so nothing is downloaded if `path` already exists.
+/// Returns whether the input was downloaded.
+#[cfg(feature = "download")]
+pub fn fetch_input(year: u32, day: u32, path: &Path) -> Result<bool> {
+    if path.exists() {
+        return Ok(false);
+    }
+
+    let session = std::env::var(SESSION_ENV_VAR)
+        .with_context(|| format!("missing session cookie in `{}`", SESSION_ENV_VAR))?;
+    let input = reqwest::blocking::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()?
+        .get(input_url(year, day))
+        .header(reqwest::header::COOKIE, format!("session={}", session))
+        .send()?
+        .error_for_status()?
+        .text()?;
+    fs::write(path, input).with_context(|| format!("failed to write {:?}", path))?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_url_format() {
+        assert_eq!(input_url(2022, 7), "https://adventofcode.com/2022/day/7/input");
+        assert_eq!(input_url(2022, 25), "https://adventofcode.com/2022/day/25/input");
+    }
+}
@@ -0,0 +1,322 @@
+//! Generates random puzzle inputs, to exercise the solutions beyond the sample and actual inputs.
+//!
+//! The same seed always yields the same input, and `size` roughly scales the input: the number
+//! of lines, groups, monkeys, etc. depending on the day.
+
+#[cfg(feature = "gen")]
+use anyhow::{bail, Result};
+#[cfg(feature = "gen")]
+use rand::rngs::StdRng;
+#[cfg(feature = "gen")]
+use rand::seq::{IndexedRandom, SliceRandom};
+#[cfg(feature = "gen")]
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "gen")]
+use std::fmt::Write;
+
+/// The item types of the rucksacks of day 3.
+#[cfg(feature = "gen")]
+const ITEM_TYPES: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The divisors of the monkeys of day 11. Their product must stay small enough for the squared
+/// worry levels of part 2 to fit in a `u64`.
+#[cfg(feature = "gen")]
+const DIVISORS: [u64; 8] = [2, 3, 5, 7, 11, 13, 17, 19];
+
+/// Returns a random puzzle input for `day`, derived from `seed`.
+#[cfg(feature = "gen")]
+pub fn generate(day: u8, seed: u64, size: usize) -> Result<String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let size = size.max(1);
+    let input = match day {
+        1 => calorie_ledger(&mut rng, size),
+        2 => strategy_guide(&mut rng, size),
+        3 => rucksacks(&mut rng, size),
+        4 => section_assignments(&mut rng, size),
+        5 => crate_stacks(&mut rng, size),
+        6 => datastream(&mut rng, size),
+        7 => shell_session(&mut rng, size),
+        8 => tree_heights(&mut rng, size),
+        9 => motions(&mut rng, size),
+        10 => program(&mut rng, size),
+        11 => monkeys(&mut rng, size),
+        _ => bail!("no generator for day {}", day),
+    };
+    Ok(input)
+}
+
+/// `size` groups of 1 to 5 calorie values, separated by blank lines.
+#[cfg(feature = "gen")]
+fn calorie_ledger(rng: &mut StdRng, size: usize) -> String {
+    let groups = (0..size)
+        .map(|_| {
+            (0..rng.random_range(1..=5))
+                .map(|_| rng.random_range(1_000..=60_000).to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect::<Vec<_>>();
+    groups.join("\n\n") + "\n"
+}
+
+/// `size` rounds of rock paper scissors.
+#[cfg(feature = "gen")]
+fn strategy_guide(rng: &mut StdRng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let opponent = *b"ABC".choose(rng).unwrap() as char;
+            let strategy = *b"XYZ".choose(rng).unwrap() as char;
+            format!("{} {}\n", opponent, strategy)
+        })
+        .collect()
+}
+
+/// `size` groups of three rucksacks sharing a badge, with an item type in both compartments.
+#[cfg(feature = "gen")]
+fn rucksacks(rng: &mut StdRng, size: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..size {
+        let badge = *ITEM_TYPES.choose(rng).unwrap();
+        for _ in 0..3 {
+            let compartment_size = rng.random_range(4..=16);
+            let mut items = (0..2 * compartment_size)
+                .map(|_| *ITEM_TYPES.choose(rng).unwrap())
+                .collect::<Vec<_>>();
+            items[rng.random_range(0..compartment_size)] = badge;
+            items[rng.random_range(compartment_size..2 * compartment_size)] = badge;
+            input.push_str(std::str::from_utf8(&items).unwrap());
+            input.push('\n');
+        }
+    }
+    input
+}
+
+/// `size` pairs of section ranges.
+#[cfg(feature = "gen")]
+fn section_assignments(rng: &mut StdRng, size: usize) -> String {
+    let range = |rng: &mut StdRng| {
+        let start = rng.random_range(1..=99);
+        (start, rng.random_range(start..=99))
+    };
+    (0..size)
+        .map(|_| {
+            let (lhs, rhs) = (range(rng), range(rng));
+            format!("{}-{},{}-{}\n", lhs.0, lhs.1, rhs.0, rhs.1)
+        })
+        .collect()
+}
+
+/// A drawing of 2 to 9 stacks of crates, followed by `size` moves. Moves never empty a stack, so
+/// every stack has a crate on top in the end.
+#[cfg(feature = "gen")]
+fn crate_stacks(rng: &mut StdRng, size: usize) -> String {
+    let stack_count = rng.random_range(2..=9);
+    let mut heights = (0..stack_count)
+        .map(|_| rng.random_range(1..=8))
+        .collect::<Vec<usize>>();
+    let max_height = *heights.iter().max().unwrap();
+
+    let mut input = String::new();
+    for level in (0..max_height).rev() {
+        let line = heights
+            .iter()
+            .map(|&height| {
+                if level < height {
+                    format!("[{}]", rng.random_range(b'A'..=b'Z') as char)
+                } else {
+                    "   ".to_owned()
+                }
+            })
+            .collect::<Vec<_>>();
+        writeln!(input, "{}", line.join(" ").trim_end()).unwrap();
+    }
+    let indexes = (1..=stack_count)
+        .map(|index| format!(" {} ", index))
+        .collect::<Vec<_>>();
+    writeln!(input, "{}\n", indexes.join(" ")).unwrap();
+
+    for _ in 0..size {
+        let sources = (0..stack_count)
+            .filter(|&index| heights[index] > 1)
+            .collect::<Vec<_>>();
+        let Some(&from) = sources.choose(rng) else {
+            break;
+        };
+        let to = (from + rng.random_range(1..stack_count)) % stack_count;
+        let count = rng.random_range(1..heights[from]);
+        heights[from] -= count;
+        heights[to] += count;
+        writeln!(input, "move {} from {} to {}", count, from + 1, to + 1).unwrap();
+    }
+    input
+}
+
+/// `size` random characters, followed by 14 distinct characters so that both markers exist.
+#[cfg(feature = "gen")]
+fn datastream(rng: &mut StdRng, size: usize) -> String {
+    let mut alphabet = (b'a'..=b'z').collect::<Vec<_>>();
+    let mut stream = (0..size)
+        .map(|_| *alphabet.choose(rng).unwrap() as char)
+        .collect::<String>();
+    alphabet.shuffle(rng);
+    stream.extend(alphabet[..14].iter().map(|&c| c as char));
+    stream + "\n"
+}
+
+/// A shell session exploring a filesystem of `size` directories, including the root.
+#[cfg(feature = "gen")]
+fn shell_session(rng: &mut StdRng, size: usize) -> String {
+    let mut input = "$ cd /\n".to_owned();
+    let mut remaining = size - 1;
+    explore_directory(rng, &mut input, &mut remaining);
+    input
+}
+
+/// Lists the content of the current directory, then explores its subdirectories, until `remaining`
+/// directories have been created.
+#[cfg(feature = "gen")]
+fn explore_directory(rng: &mut StdRng, input: &mut String, remaining: &mut usize) {
+    let subdirectories = rng.random_range((*remaining).min(1)..=(*remaining).min(3));
+    *remaining -= subdirectories;
+
+    input.push_str("$ ls\n");
+    for index in 0..subdirectories {
+        writeln!(input, "dir d{}", index).unwrap();
+    }
+    for index in 0..rng.random_range(0..=4) {
+        let size = rng.random_range(1_000..=300_000);
+        writeln!(input, "{} f{}.txt", size, index).unwrap();
+    }
+    for index in 0..subdirectories {
+        writeln!(input, "$ cd d{}", index).unwrap();
+        explore_directory(rng, input, remaining);
+        input.push_str("$ cd ..\n");
+    }
+}
+
+/// A `size` by `size` map of tree heights.
+#[cfg(feature = "gen")]
+fn tree_heights(rng: &mut StdRng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let mut row = (0..size)
+                .map(|_| rng.random_range(b'0'..=b'9') as char)
+                .collect::<String>();
+            row.push('\n');
+            row
+        })
+        .collect()
+}
+
+/// `size` motions of the head of the rope.
+#[cfg(feature = "gen")]
+fn motions(rng: &mut StdRng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let direction = *b"UDLR".choose(rng).unwrap() as char;
+            format!("{} {}\n", direction, rng.random_range(1..=20))
+        })
+        .collect()
+}
+
+/// A program of `size` instructions.
+#[cfg(feature = "gen")]
+fn program(rng: &mut StdRng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            if rng.random_bool(0.3) {
+                "noop\n".to_owned()
+            } else {
+                format!("addx {}\n", rng.random_range(-20..=20))
+            }
+        })
+        .collect()
+}
+
+/// `size` monkeys, at least 2 and at most one per divisor, with one of them squaring the worry
+/// levels.
+#[cfg(feature = "gen")]
+fn monkeys(rng: &mut StdRng, size: usize) -> String {
+    let count = size.clamp(2, DIVISORS.len());
+    let mut divisors = DIVISORS;
+    divisors.shuffle(rng);
+    let squaring = rng.random_range(0..count);
+
+    let blocks = (0..count)
+        .map(|index| {
+            let items = (0..rng.random_range(0..=4))
+                .map(|_| rng.random_range(50..=99).to_string())
+                .collect::<Vec<_>>();
+            let operation = if index == squaring {
+                "old * old".to_owned()
+            } else if rng.random_bool(0.5) {
+                format!("old * {}", rng.random_range(2..=19))
+            } else {
+                format!("old + {}", rng.random_range(1..=8))
+            };
+            let mut target = || (index + rng.random_range(1..count)) % count;
+            format!(
+                concat!(
+                    "Monkey {}:\n",
+                    "  Starting items: {}\n",
+                    "  Operation: new = {}\n",
+                    "  Test: divisible by {}\n",
+                    "    If true: throw to monkey {}\n",
+                    "    If false: throw to monkey {}\n",
+                ),
+                index,
+                items.join(", "),
+                operation,
+                divisors[index],
+                target(),
+                target()
+            )
+        })
+        .collect::<Vec<_>>();
+    blocks.join("\n")
+}
+
+#[cfg(all(test, feature = "gen"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_input() {
+        for day in 1..=11 {
+            assert_eq!(generate(day, 42, 10).unwrap(), generate(day, 42, 10).unwrap());
+            assert_ne!(generate(day, 42, 10).unwrap(), generate(day, 43, 10).unwrap());
+        }
+    }
+
+    #[test]
+    fn unknown_day() {
+        assert_eq!(generate(12, 0, 10).unwrap_err().to_string(), "no generator for day 12");
+    }
+
+    #[test]
+    fn rucksacks_share_a_badge() {
+        let input = generate(3, 7, 20).unwrap();
+        let lines = input.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 60);
+        for group in lines.chunks(3) {
+            assert!(group[0]
+                .chars()
+                .any(|c| group[1].contains(c) && group[2].contains(c)));
+        }
+        for line in lines {
+            let (lhs, rhs) = line.split_at(line.len() / 2);
+            assert!(lhs.chars().any(|c| rhs.contains(c)));
+        }
+    }
+
+    #[test]
+    fn datastream_ends_with_a_marker() {
+        let input = generate(6, 7, 100).unwrap();
+        let stream = input.trim_end().as_bytes();
+        let mut marker = stream[stream.len() - 14..].to_vec();
+        marker.sort_unstable();
+        marker.dedup();
+        assert_eq!(stream.len(), 114);
+        assert_eq!(marker.len(), 14);
+    }
+}
@@ -0,0 +1,424 @@
+//! A rectangular grid of cells stored in row-major order, a renderer for sparse sets of points,
+//! the cardinal directions to walk them, and the distances between points.
+
+use anyhow::{anyhow, Error, Result};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// The offsets of the 4 orthogonal neighbors of a cell, in reading order.
+const OFFSETS4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// The offsets of the 8 orthogonal and diagonal neighbors of a cell, in reading order.
+const OFFSETS8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// A rectangular grid of cells, addressed by `(x, y)` coordinates from the top-left corner.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+}
+
+impl<T> Grid<T> {
+    /// Creates a grid of `width` columns from `cells` in row-major order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is 0, or if `cells` doesn't hold a whole number of rows.
+    pub fn new(cells: Vec<T>, width: usize) -> Self {
+        assert!(width > 0, "a grid must have at least one column");
+        assert!(cells.len().is_multiple_of(width), "incomplete last row");
+        Grid { cells, width }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len() / self.width
+    }
+
+    /// Returns the cells in row-major order.
+    pub fn into_cells(self) -> Vec<T> {
+        self.cells
+    }
+
+    /// Returns the cell at `(x, y)`, or `None` if out of the grid.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        (x < self.width && y < self.height()).then(|| &self.cells[y * self.width + x])
+    }
+
+    /// Returns the coordinates of the orthogonal neighbors of `(x, y)` that are within the grid,
+    /// in reading order.
+    pub fn neighbors4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        neighbors(&OFFSETS4, x, y, self.width, self.height())
+    }
+
+    /// Returns the coordinates of the orthogonal and diagonal neighbors of `(x, y)` that are within
+    /// the grid, in reading order.
+    pub fn neighbors8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        neighbors(&OFFSETS8, x, y, self.width, self.height())
+    }
+}
+
+/// Parses a grid of single digits, one row per line. The width is the length of the first line,
+/// and every other line must be as wide. Blank lines are ignored.
+pub fn parse_grid_of_digits(input: &str) -> Result<Grid<u8>> {
+    let input = input.as_bytes();
+    let width = input
+        .iter()
+        .position(|b| *b == b'\n')
+        .unwrap_or(input.len());
+    if width == 0 {
+        return Err(anyhow!("empty grid"));
+    }
+
+    let mut cells = Vec::with_capacity(input.len());
+    for (line_index, line) in input.split(|b| *b == b'\n').enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        if line.len() != width {
+            return Err(anyhow!(
+                "line {}: expected {} digits, got {}",
+                line_index + 1,
+                width,
+                line.len()
+            ));
+        }
+        for digit in line {
+            match digit {
+                b'0'..=b'9' => cells.push(digit - b'0'),
+                _ => {
+                    return Err(anyhow!(
+                        "line {}: invalid digit {:?}",
+                        line_index + 1,
+                        char::from(*digit)
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(Grid::new(cells, width))
+}
+
+/// Applies `offsets` to `(x, y)`, keeping only the coordinates within a `width` by `height` grid.
+fn neighbors(
+    offsets: &'static [(isize, isize)],
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    offsets.iter().filter_map(move |&(dx, dy)| {
+        let x = x.checked_add_signed(dx).filter(|x| *x < width)?;
+        let y = y.checked_add_signed(dy).filter(|y| *y < height)?;
+        Some((x, y))
+    })
+}
+
+/// Returns the Manhattan distance between `a` and `b`, ie. the number of orthogonal steps from one
+/// to the other.
+pub fn manhattan(a: (i64, i64), b: (i64, i64)) -> i64 {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs()
+}
+
+/// Returns the Chebyshev distance between `a` and `b`, ie. the number of steps from one to the
+/// other when diagonal steps are allowed. Points at a distance of at most 1 touch.
+pub fn chebyshev(a: (i64, i64), b: (i64, i64)) -> i64 {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs())
+}
+
+/// Renders `points` within their bounding box, one line per row, using `on` for the points and
+/// `off` for the rest. Up is the positive `y`, so the first line is the highest row.
+pub fn render_points(points: &HashSet<(i64, i64)>, on: char, off: char) -> String {
+    let (min_x, max_x, min_y, max_y) = match points.iter().next() {
+        None => return String::new(),
+        Some(&(x, y)) => points
+            .iter()
+            .fold((x, x, y, y), |(x0, x1, y0, y1), &(x, y)| {
+                (x0.min(x), x1.max(x), y0.min(y), y1.max(y))
+            }),
+    };
+
+    (min_y..=max_y)
+        .rev()
+        .map(|y| {
+            (min_x..=max_x)
+                .map(|x| if points.contains(&(x, y)) { on } else { off })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A cardinal direction on a plane where up is the positive `y`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    /// All the directions, clockwise from `Up`.
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /// Returns the `(dx, dy)` step of a single move in this direction.
+    pub fn delta(&self) -> (i64, i64) {
+        match self {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    /// Returns the direction after a quarter turn counterclockwise.
+    pub fn turn_left(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
+        }
+    }
+
+    /// Returns the direction after a quarter turn clockwise.
+    pub fn turn_right(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+/// Parses the `U`, `D`, `L` and `R` notation of the puzzles.
+impl FromStr for Direction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "U" => Ok(Direction::Up),
+            "D" => Ok(Direction::Down),
+            "L" => Ok(Direction::Left),
+            "R" => Ok(Direction::Right),
+            _ => Err(anyhow!("invalid direction: {:?}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4 by 3 grid.
+    fn grid() -> Grid<u8> {
+        Grid::new((0..12).collect(), 4)
+    }
+
+    #[test]
+    fn manhattan_distances() {
+        assert_eq!(manhattan((0, 0), (0, 0)), 0);
+        assert_eq!(manhattan((0, 0), (3, 4)), 7);
+        assert_eq!(manhattan((3, 4), (0, 0)), 7);
+        assert_eq!(manhattan((-2, 5), (1, -1)), 9);
+        assert_eq!(manhattan((1, 1), (2, 2)), 2);
+    }
+
+    #[test]
+    fn chebyshev_distances() {
+        assert_eq!(chebyshev((0, 0), (0, 0)), 0);
+        assert_eq!(chebyshev((0, 0), (3, 4)), 4);
+        assert_eq!(chebyshev((3, 4), (0, 0)), 4);
+        assert_eq!(chebyshev((-2, 5), (1, -1)), 6);
+        // Diagonal neighbors touch.
+        assert_eq!(chebyshev((1, 1), (2, 2)), 1);
+        assert!(chebyshev((0, 0), (2, 1)) > 1);
+    }
+
+    #[test]
+    fn dimensions_and_get() {
+        let grid = grid();
+
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        assert_eq!(grid.get(1, 2), Some(&9));
+        assert_eq!(grid.get(4, 0), None);
+        assert_eq!(grid.get(0, 3), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn incomplete_row() {
+        Grid::new(vec![1, 2, 3], 2);
+    }
+
+    #[test]
+    fn neighbors_at_corners() {
+        let grid = grid();
+
+        assert_eq!(grid.neighbors4(0, 0).collect::<Vec<_>>(), [(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbors8(0, 0).collect::<Vec<_>>(), [(1, 0), (0, 1), (1, 1)]);
+        assert_eq!(grid.neighbors4(3, 2).collect::<Vec<_>>(), [(3, 1), (2, 2)]);
+        assert_eq!(grid.neighbors8(3, 2).collect::<Vec<_>>(), [(2, 1), (3, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn neighbors_at_edges() {
+        let grid = grid();
+
+        assert_eq!(grid.neighbors4(2, 0).collect::<Vec<_>>(), [(1, 0), (3, 0), (2, 1)]);
+        assert_eq!(
+            grid.neighbors8(0, 1).collect::<Vec<_>>(),
+            [(0, 0), (1, 0), (1, 1), (0, 2), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn neighbors_in_interior() {
+        let grid = grid();
+
+        assert_eq!(grid.neighbors4(1, 1).collect::<Vec<_>>(), [(1, 0), (0, 1), (2, 1), (1, 2)]);
+        assert_eq!(
+            grid.neighbors8(2, 1).collect::<Vec<_>>(),
+            [
+                (1, 0),
+                (2, 0),
+                (3, 0),
+                (1, 1),
+                (3, 1),
+                (1, 2),
+                (2, 2),
+                (3, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn neighbors_of_single_cell() {
+        let grid = Grid::new(vec![0], 1);
+
+        assert_eq!(grid.neighbors4(0, 0).count(), 0);
+        assert_eq!(grid.neighbors8(0, 0).count(), 0);
+    }
+
+    #[test]
+    fn parse_valid_grid_of_digits() {
+        let grid = parse_grid_of_digits("303\n255\n\n653\n").unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid.get(2, 1), Some(&5));
+        assert_eq!(grid.into_cells(), [3, 0, 3, 2, 5, 5, 6, 5, 3]);
+        assert_eq!(parse_grid_of_digits("7").unwrap().into_cells(), [7]);
+    }
+
+    #[test]
+    fn parse_ragged_grid_of_digits() {
+        assert_eq!(parse_grid_of_digits("").unwrap_err().to_string(), "empty grid");
+        assert_eq!(
+            parse_grid_of_digits("123\n45\n").unwrap_err().to_string(),
+            "line 2: expected 3 digits, got 2"
+        );
+        assert_eq!(
+            parse_grid_of_digits("12\n345\n").unwrap_err().to_string(),
+            "line 2: expected 2 digits, got 3"
+        );
+    }
+
+    #[test]
+    fn parse_grid_of_non_digits() {
+        assert_eq!(
+            parse_grid_of_digits("123\n4x6\n").unwrap_err().to_string(),
+            "line 2: invalid digit 'x'"
+        );
+        assert_eq!(
+            parse_grid_of_digits("12\r\n34\r\n")
+                .unwrap_err()
+                .to_string(),
+            "line 1: invalid digit '\\r'"
+        );
+    }
+
+    #[test]
+    fn render_no_points() {
+        assert_eq!(render_points(&HashSet::new(), '#', '.'), "");
+    }
+
+    #[test]
+    fn render_single_point() {
+        assert_eq!(render_points(&HashSet::from([(-3, 7)]), '#', '.'), "#");
+    }
+
+    #[test]
+    fn render_l_shaped_points() {
+        let points = HashSet::from([(0, 0), (0, 1), (0, 2), (1, 0), (2, 0)]);
+
+        assert_eq!(render_points(&points, '#', '.'), "#..\n#..\n###");
+        assert_eq!(render_points(&points, 'o', ' '), "o  \no  \nooo");
+    }
+
+    #[test]
+    fn direction_deltas() {
+        assert_eq!(Direction::Up.delta(), (0, 1));
+        assert_eq!(Direction::Down.delta(), (0, -1));
+        assert_eq!(Direction::Left.delta(), (-1, 0));
+        assert_eq!(Direction::Right.delta(), (1, 0));
+    }
+
+    #[test]
+    fn direction_turns() {
+        for direction in Direction::ALL {
+            let mut left = direction;
+            let mut right = direction;
+            for _ in 0..4 {
+                left = left.turn_left();
+                right = right.turn_right();
+            }
+            assert_eq!(left, direction);
+            assert_eq!(right, direction);
+
+            assert_eq!(direction.turn_left().turn_right(), direction);
+            assert_eq!(direction.turn_right().turn_right(), direction.opposite());
+            assert_eq!(direction.opposite().opposite(), direction);
+            let ((dx, dy), (ox, oy)) = (direction.delta(), direction.opposite().delta());
+            assert_eq!((dx + ox, dy + oy), (0, 0));
+        }
+    }
+
+    #[test]
+    fn direction_from_str() {
+        assert_eq!("U".parse::<Direction>().unwrap(), Direction::Up);
+        assert_eq!("D".parse::<Direction>().unwrap(), Direction::Down);
+        assert_eq!("L".parse::<Direction>().unwrap(), Direction::Left);
+        assert_eq!("R".parse::<Direction>().unwrap(), Direction::Right);
+        assert!("u".parse::<Direction>().is_err());
+        assert!("".parse::<Direction>().is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! Locates and reads the puzzle inputs stored in `puzzles/`, and provides helpers working on their
+//! contents: `checksum`, and counting the lines that match a predicate.
+
+use anyhow::{anyhow, Context, Result};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The environment variable overriding the directory holding the puzzle inputs.
+pub const INPUT_DIR_ENV_VAR: &str = "AOC_INPUT_DIR";
+
+/// Which of the puzzle inputs of a day to read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputKind {
+    /// The example given in the puzzle description, `puzzles/dayNN.test`.
+    Sample,
+    /// The actual puzzle input, `puzzles/dayNN.prod`.
+    Prod,
+}
+
+impl InputKind {
+    fn extension(self) -> &'static str {
+        match self {
+            InputKind::Sample => "test",
+            InputKind::Prod => "prod",
+        }
+    }
+}
+
+// Selects the puzzle input to read at runtime. Reads the actual puzzle input by default.
+#[derive(clap::Args, Debug)]
+pub struct InputArgs {
+    // Read the example from the puzzle description, `puzzles/dayNN.test`.
+    #[clap(long = "sample", conflicts_with = "prod")]
+    sample: bool,
+
+    // Read the actual puzzle input, `puzzles/dayNN.prod`. This is the default.
+    #[clap(long = "prod")]
+    prod: bool,
+
+    // Read the puzzle inputs from the given directory instead of `puzzles/`.
+    #[clap(long = "input-dir", env = INPUT_DIR_ENV_VAR, value_name = "DIR")]
+    input_dir: Option<PathBuf>,
+
+    // Print a checksum of the puzzle input on stderr before solving, to tell whether two runs read
+    // the same input.
+    #[clap(long = "checksum")]
+    checksum: bool,
+}
+
+impl InputArgs {
+    pub fn kind(&self) -> InputKind {
+        if self.sample {
+            InputKind::Sample
+        } else {
+            InputKind::Prod
+        }
+    }
+
+    /// Returns the path of the selected puzzle input of `day`.
+    pub fn path(&self, day: u8) -> PathBuf {
+        let dir = self.input_dir.as_deref().unwrap_or_else(|| puzzles_dir());
+        input_path_in(dir, day, self.kind())
+    }
+
+    /// Reads the selected puzzle input of `day`, printing its checksum if requested.
+    pub fn load(&self, day: u8) -> Result<String> {
+        let input = read(&self.path(day))?;
+        if self.checksum {
+            eprintln!("checksum: {:016x}", checksum(&input));
+        }
+        Ok(input)
+    }
+}
+
+/// Returns the directory holding the puzzle inputs.
+pub fn puzzles_dir() -> &'static Path {
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/puzzles"))
+}
+
+/// Returns the path of the `kind` puzzle input of `day`.
+pub fn input_path(day: u8, kind: InputKind) -> PathBuf {
+    input_path_in(puzzles_dir(), day, kind)
+}
+
+/// Returns the path of the `kind` puzzle input of `day` within `dir`.
+pub fn input_path_in(dir: &Path, day: u8, kind: InputKind) -> PathBuf {
+    dir.join(format!("day{:02}.{}", day, kind.extension()))
+}
+
+/// Reads the `kind` puzzle input of `day`.
+pub fn load(day: u8, kind: InputKind) -> Result<String> {
+    read(&input_path(day, kind))
+}
+
+/// Returns the 64 bits FNV-1a hash of `input`, ignoring a trailing newline so that it doesn't
+/// depend on the editor used to save the input. Unlike `DefaultHasher`, the hash is the same
+/// across Rust releases.
+pub fn checksum(input: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let input = input
+        .strip_suffix('\n')
+        .map(|input| input.strip_suffix('\r').unwrap_or(input))
+        .unwrap_or(input);
+    input
+        .bytes()
+        .fold(OFFSET_BASIS, |hash, byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
+
+/// Parses each line of `input` as a `T` and counts those matching `predicate`. Lines that don't
+/// parse are skipped, see `try_count_lines_matching` to reject them instead.
+pub fn count_lines_matching<T, P>(input: &str, predicate: P) -> usize
+where
+    T: FromStr,
+    P: Fn(&T) -> bool,
+{
+    input
+        .lines()
+        .filter_map(|line| line.parse().ok())
+        .filter(|value| predicate(value))
+        .count()
+}
+
+/// Parses each line of `input` as a `T` and counts those matching `predicate`, or returns an error
+/// naming the first line that doesn't parse. Blank lines are skipped.
+pub fn try_count_lines_matching<T, P>(input: &str, predicate: P) -> Result<usize>
+where
+    T: FromStr,
+    <T as FromStr>::Err: fmt::Debug,
+    P: Fn(&T) -> bool,
+{
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .try_fold(0, |count, (index, line)| {
+            let value = line
+                .parse()
+                .map_err(|e| anyhow!("line {}: {:?}: {:?}", index + 1, line, e))?;
+            Ok(count + predicate(&value) as usize)
+        })
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("failed to read {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cmdline {
+        #[clap(flatten)]
+        input: InputArgs,
+    }
+
+    fn input_args(args: &[&str]) -> InputArgs {
+        Cmdline::try_parse_from(args).unwrap().input
+    }
+
+    fn kind(args: &[&str]) -> InputKind {
+        input_args(args).kind()
+    }
+
+    #[test]
+    fn input_paths() {
+        assert!(input_path(7, InputKind::Sample).ends_with("puzzles/day07.test"));
+        assert!(input_path(7, InputKind::Prod).ends_with("puzzles/day07.prod"));
+        assert!(input_path(11, InputKind::Sample).ends_with("puzzles/day11.test"));
+        assert!(input_path(7, InputKind::Sample).exists());
+        assert!(input_path(7, InputKind::Prod).exists());
+    }
+
+    #[test]
+    fn defaults_to_prod() {
+        assert_eq!(kind(&["dayNN"]), InputKind::Prod);
+        assert_eq!(kind(&["dayNN", "--prod"]), InputKind::Prod);
+        assert_eq!(kind(&["dayNN", "--sample"]), InputKind::Sample);
+        assert!(Cmdline::try_parse_from(["dayNN", "--sample", "--prod"]).is_err());
+    }
+
+    #[test]
+    fn input_dir_override() {
+        let default = input_args(&["dayNN", "--sample"]);
+        let custom = input_args(&["dayNN", "--sample", "--input-dir", "/tmp/inputs"]);
+
+        // `AOC_INPUT_DIR` may be set in the environment running the tests.
+        if std::env::var_os(INPUT_DIR_ENV_VAR).is_none() {
+            assert_eq!(default.path(7), input_path(7, InputKind::Sample));
+        }
+        assert_eq!(custom.path(7), Path::new("/tmp/inputs/day07.test"));
+        assert_eq!(
+            input_path_in(Path::new("inputs"), 11, InputKind::Prod),
+            Path::new("inputs/day11.prod")
+        );
+    }
+
+    #[test]
+    fn checksum_ignores_trailing_newline() {
+        assert_eq!(checksum("R 4\nU 4"), checksum("R 4\nU 4\n"));
+        assert_eq!(checksum("R 4\nU 4"), checksum("R 4\nU 4\r\n"));
+        assert_ne!(checksum("R 4\nU 4"), checksum("R 4\nU 4\n\n"));
+        assert_ne!(checksum("R 4\nU 4"), checksum("R 4\nU 5"));
+        assert_ne!(checksum("R 4\nU 4"), checksum("U 4\nR 4"));
+    }
+
+    #[test]
+    fn checksum_is_stable() {
+        // Known FNV-1a values, which must not change between runs or releases.
+        assert_eq!(checksum(""), 0xcbf29ce484222325);
+        assert_eq!(checksum("a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(checksum("foobar\n"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn count_lines_matching_skips_invalid_lines() {
+        let input = "3\n-1\nfour\n8\n\n12";
+
+        assert_eq!(count_lines_matching(input, |value: &u8| *value > 5), 2);
+        assert_eq!(count_lines_matching(input, |value: &i8| *value < 5), 2);
+        assert_eq!(count_lines_matching(input, |_: &u8| true), 3);
+        assert_eq!(count_lines_matching("", |_: &u8| true), 0);
+    }
+
+    #[test]
+    fn try_count_lines_matching_rejects_invalid_lines() {
+        let err = try_count_lines_matching("3\n-1\n8", |value: &u8| *value > 5).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: \"-1\": ParseIntError { kind: InvalidDigit }");
+
+        let input = "3\n-1\n8\n\n12\n";
+        assert_eq!(try_count_lines_matching(input, |value: &i8| *value > 5).unwrap(), 2);
+        assert_eq!(
+            try_count_lines_matching(input, |value: &i8| *value > 5).unwrap(),
+            count_lines_matching(input, |value: &i8| *value > 5)
+        );
+    }
+
+    #[test]
+    fn load_sample() {
+        assert_eq!(load(10, InputKind::Sample).unwrap(), include_str!("../puzzles/day10.test"));
+        assert!(load(1, InputKind::Sample).is_err());
+    }
+}
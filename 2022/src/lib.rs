@@ -0,0 +1,27 @@
+//! Helpers shared by the daily solutions in `src/bin`.
+
+extern crate anyhow;
+extern crate clap;
+#[cfg(feature = "viz")]
+extern crate owo_colors;
+#[cfg(feature = "gen")]
+extern crate rand;
+#[cfg(feature = "download")]
+extern crate reqwest;
+extern crate serde;
+extern crate serde_json;
+#[cfg(feature = "tracing")]
+pub extern crate tracing;
+#[cfg(feature = "tracing")]
+extern crate tracing_subscriber;
+
+pub mod allocs;
+pub mod download;
+pub mod generate;
+pub mod grid;
+pub mod input;
+pub mod log;
+pub mod math;
+pub mod pool;
+pub mod runner;
+pub mod viz;
@@ -0,0 +1,101 @@
+//! Opt-in structured logs of the parsing and solving steps of each day.
+//!
+//! Everything here compiles to nothing unless the `tracing` feature is enabled, so the solutions
+//! can log freely: `log_debug!` and `log_trace!` take the arguments of their `tracing`
+//! counterparts.
+
+/// Logs an event at the `debug` level.
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        $crate::tracing::debug!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! log_debug {
+    ($($arg:tt)*) => {};
+}
+
+/// Logs an event at the `trace` level.
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! log_trace {
+    ($($arg:tt)*) => {
+        $crate::tracing::trace!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! log_trace {
+    ($($arg:tt)*) => {};
+}
+
+/// Keeps the span of a day entered until dropped.
+pub struct DaySpan {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+/// Installs a subscriber printing the events up to `level` on stderr, if any, and enters the span
+/// of `day`.
+#[cfg(feature = "tracing")]
+pub fn init(level: Option<tracing::Level>, day: Option<u8>) -> DaySpan {
+    if let Some(level) = level {
+        tracing_subscriber::fmt()
+            .with_max_level(level)
+            .with_writer(std::io::stderr)
+            .init();
+    }
+    DaySpan {
+        _span: tracing::info_span!("day", day).entered(),
+    }
+}
+
+/// Runs `f` within a span named after `step`, eg. `parse`.
+#[cfg(feature = "tracing")]
+pub fn in_span<T>(step: &'static str, f: impl FnOnce() -> T) -> T {
+    let _span = tracing::debug_span!("step", step).entered();
+    f()
+}
+
+#[cfg(not(feature = "tracing"))]
+pub fn in_span<T>(_step: &'static str, f: impl FnOnce() -> T) -> T {
+    f()
+}
+
+/// Runs `f` with every event up to `level` formatted, but discarded. Lets tests check that logging
+/// has no side effect.
+#[cfg(feature = "tracing")]
+pub fn with_discarded_logs<T>(level: tracing::Level, f: impl FnOnce() -> T) -> T {
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::sink)
+        .finish();
+    tracing::subscriber::with_default(subscriber, f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_span_returns_result() {
+        assert_eq!(in_span("parse", || 42), 42);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn discarded_logs_keep_result() {
+        let answer = with_discarded_logs(tracing::Level::TRACE, || {
+            in_span("solve", || {
+                log_debug!(answer = 42, "solved");
+                42
+            })
+        });
+        assert_eq!(answer, 42);
+    }
+}
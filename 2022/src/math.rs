@@ -0,0 +1,45 @@
+//! Small numeric helpers shared by the daily solutions.
+
+/// Returns the product of the two largest values of `values`, found in a single pass.
+///
+/// Missing values count as 1, as in an empty product: a single value is returned as-is, and no
+/// values at all yield 1.
+pub fn top_two_product(values: &[u64]) -> u64 {
+    let (mut first, mut second) = (None, None);
+    for &value in values {
+        if first.is_none_or(|first| value > first) {
+            second = first;
+            first = Some(value);
+        } else if second.is_none_or(|second| value > second) {
+            second = Some(value);
+        }
+    }
+    first.unwrap_or(1) * second.unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_two_product_fewer_than_two() {
+        assert_eq!(top_two_product(&[]), 1);
+        assert_eq!(top_two_product(&[7]), 7);
+        assert_eq!(top_two_product(&[0]), 0);
+    }
+
+    #[test]
+    fn top_two_product_unsorted() {
+        assert_eq!(top_two_product(&[101, 95, 7, 105]), 10605);
+        assert_eq!(top_two_product(&[1, 2, 3, 4, 5]), 20);
+        assert_eq!(top_two_product(&[5, 4, 3, 2, 1]), 20);
+    }
+
+    #[test]
+    fn top_two_product_ties() {
+        assert_eq!(top_two_product(&[3, 3]), 9);
+        assert_eq!(top_two_product(&[2, 5, 5, 1]), 25);
+        assert_eq!(top_two_product(&[5, 2, 2]), 10);
+        assert_eq!(top_two_product(&[0, 0, 0]), 0);
+    }
+}
@@ -0,0 +1,83 @@
+//! Runs independent jobs on a bounded number of threads.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Calls `job` on each of `inputs` using at most `jobs` threads, and returns the results in the
+/// order of `inputs`.
+///
+/// Nothing is printed from here: callers print the results once all jobs are done, so the output
+/// of concurrent jobs never interleaves.
+pub fn map_ordered<I, T, F>(inputs: &[I], jobs: usize, job: F) -> Vec<T>
+where
+    I: Sync,
+    T: Send,
+    F: Fn(&I) -> T + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..inputs.len()).map(|_| None).collect::<Vec<_>>());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, inputs.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let input = match inputs.get(index) {
+                    Some(input) => input,
+                    None => break,
+                };
+                let result = job(input);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every job ran"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn concurrent_matches_sequential() {
+        let inputs = (1..=25).collect::<Vec<u64>>();
+        // Later inputs finish first, so completion order differs from input order.
+        let job = |n: &u64| {
+            thread::sleep(Duration::from_millis(26 - n));
+            n * n
+        };
+
+        let sequential = map_ordered(&inputs, 1, job);
+        assert_eq!(sequential, inputs.iter().map(|n| n * n).collect::<Vec<_>>());
+        for jobs in [2, 4, 25, 100] {
+            assert_eq!(map_ordered(&inputs, jobs, job), sequential);
+        }
+    }
+
+    #[test]
+    fn bounded_threads() {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+
+        map_ordered(&[(); 12], 3, |_| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(5));
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+
+        assert!(max_running.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn no_inputs() {
+        assert!(map_ordered(&[] as &[u8], 4, |n| *n).is_empty());
+    }
+}
@@ -0,0 +1,428 @@
+use std::env;
+use std::fmt::Display;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "allocs")]
+use allocs;
+use log;
+
+/// The parts of the challenge to run, in order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChallengeParts(Vec<usize>);
+
+impl ChallengeParts {
+    pub fn parts(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+/// Parses the parts of the challenge to run: either `all`, `stage1` and `stage2`, or a
+/// comma-separated list of parts such as `1` or `2,1`.
+pub fn parse_challenge_parts(spec: &str) -> Result<ChallengeParts, String> {
+    let parts = match spec {
+        "all" => vec![1, 2],
+        "stage1" => vec![1],
+        "stage2" => vec![2],
+        _ => {
+            let mut parts = vec![];
+            for part in spec.split(',') {
+                let part = match part.trim() {
+                    "" => return Err("empty part in list".to_owned()),
+                    "1" => 1,
+                    "2" => 2,
+                    part => return Err(format!("unknown part {:?}, expected 1 or 2", part)),
+                };
+                if parts.contains(&part) {
+                    return Err(format!("part {} is listed more than once", part));
+                }
+                parts.push(part);
+            }
+            parts
+        }
+    };
+
+    Ok(ChallengeParts(parts))
+}
+
+// The command line arguments shared by all the daily solutions, to be flattened into their own.
+// Not a doc comment, as clap would use it as the description of every solution.
+#[derive(clap::Args, Debug)]
+pub struct RunnerArgs {
+    // The parts of the challenge to run: `all`, `stage1`, `stage2`, or a list such as `2,1`.
+    // Defaults to all stages.
+    #[clap(short = 'c', long = "challenge", default_value = "all", value_parser = parse_challenge_parts)]
+    pub challenge: ChallengeParts,
+
+    // Run each part this many times on the same parsed input, and print timings on stderr.
+    #[clap(long = "repeat", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub repeat: u32,
+
+    // How to print the answers.
+    #[clap(long = "format", value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    // Print the puzzle input as parsed instead of solving the puzzle.
+    #[clap(long = "dump-parsed")]
+    pub dump_parsed: bool,
+
+    // Log the parsing and solving steps on stderr, up to the given level (`error`, `warn`, `info`,
+    // `debug` or `trace`).
+    #[cfg(feature = "tracing")]
+    #[clap(long = "log-level", value_name = "LEVEL")]
+    pub log_level: Option<tracing::Level>,
+
+    // Print the number of heap allocations and allocated bytes on stderr after solving.
+    #[cfg(feature = "allocs")]
+    #[clap(long = "profile-allocs")]
+    pub profile_allocs: bool,
+}
+
+impl RunnerArgs {
+    /// Installs the logger selected by `--log-level`, and enters the span of the day being solved.
+    /// The span lasts until the returned value is dropped.
+    #[cfg(feature = "tracing")]
+    pub fn init_logging(&self) -> log::DaySpan {
+        log::init(self.log_level, program_day())
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub fn init_logging(&self) -> log::DaySpan {
+        log::DaySpan {}
+    }
+
+    /// Prints the parsed puzzle input as rendered by `render` if `--dump-parsed` is set, and
+    /// returns whether it did, in which case the puzzle must not be solved.
+    pub fn print_parsed(&self, render: impl FnOnce() -> String) -> bool {
+        if self.dump_parsed {
+            println!("{}", render().trim_end());
+        }
+        self.dump_parsed
+    }
+}
+
+/// How the answers are printed.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// One answer per line, labeled with its part when running all stages.
+    Text,
+    /// A single `SolveReport` serialized as JSON.
+    Json,
+}
+
+/// The machine-readable outcome of a run. Parts that were not run have no answer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SolveReport {
+    /// The day of the challenge, inferred from the name of the binary.
+    pub day: Option<u8>,
+    pub part1: Option<String>,
+    pub part2: Option<String>,
+    /// The wall time of the last run of each part, in milliseconds.
+    pub ms: f64,
+    /// The wall time of the last run of part 1, in microseconds, excluding reading and parsing the
+    /// input when the solution parses it upfront.
+    #[serde(default)]
+    pub part1_micros: Option<u64>,
+    /// Same as `part1_micros`, for part 2.
+    #[serde(default)]
+    pub part2_micros: Option<u64>,
+}
+
+/// Returns the day solved by the binary named `program`, eg. 7 for `target/debug/day07`.
+fn day_of_program(program: &Path) -> Option<u8> {
+    program
+        .file_stem()?
+        .to_str()?
+        .strip_prefix("day")?
+        .parse()
+        .ok()
+}
+
+/// Returns the day solved by the running binary, if it is one of the daily solutions.
+fn program_day() -> Option<u8> {
+    env::args_os()
+        .next()
+        .and_then(|program| day_of_program(Path::new(&program)))
+}
+
+/// Wall time statistics over repeated runs of a part.
+#[derive(Debug, PartialEq)]
+pub struct Timings {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+}
+
+impl Timings {
+    /// Computes the statistics of `samples`, or `None` if there are none.
+    pub fn from_samples(samples: &[Duration]) -> Option<Timings> {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let count = sorted.len();
+        let median = match count {
+            0 => return None,
+            _ if count.is_multiple_of(2) => (sorted[count / 2 - 1] + sorted[count / 2]) / 2,
+            _ => sorted[count / 2],
+        };
+
+        Some(Timings {
+            min: sorted[0],
+            median,
+            mean: sorted.iter().sum::<Duration>() / count as u32,
+        })
+    }
+}
+
+/// Runs `solve` `repeat` times and returns the answer and wall time of the last run. Timings are
+/// printed on stderr when running more than once, so that stdout is the same as a single run.
+fn run_part<T>(part: usize, repeat: u32, solve: impl Fn() -> T) -> (T, Duration) {
+    let mut samples = vec![];
+    let mut answer = None;
+    for _ in 0..repeat.max(1) {
+        let start = Instant::now();
+        answer = Some(log::in_span(if part == 1 { "part1" } else { "part2" }, &solve));
+        samples.push(start.elapsed());
+    }
+    let last = *samples.last().expect("at least one run");
+
+    if repeat > 1 {
+        let timings = Timings::from_samples(&samples).expect("at least one run");
+        eprintln!(
+            "Part {}: min {:?}, median {:?}, mean {:?} over {} runs",
+            part, timings.min, timings.median, timings.mean, repeat
+        );
+    }
+    (answer.expect("at least one run"), last)
+}
+
+/// Prefixes `answer` with the part it answers. Multi-line answers start on their own line.
+fn labeled(part: usize, answer: impl Display) -> String {
+    let answer = answer.to_string();
+    if answer.contains('\n') {
+        format!("Part {}:\n{}", part, answer)
+    } else {
+        format!("Part {}: {}", part, answer)
+    }
+}
+
+/// Runs the parts of the challenge selected by `args.challenge` in order, and prints their answers
+/// in `args.format`.
+///
+/// In text, a single part prints its answer alone, while several parts label each answer with its
+/// part number.
+pub fn run<A, B>(args: &RunnerArgs, part1: impl Fn() -> A, part2: impl Fn() -> B)
+where
+    A: Display,
+    B: Display,
+{
+    let repeat = args.repeat;
+    #[cfg(feature = "allocs")]
+    let allocs_before = allocs::stats();
+    let answers = args
+        .challenge
+        .parts()
+        .iter()
+        .map(|&part| {
+            let (answer, elapsed) = match part {
+                1 => {
+                    let (answer, elapsed) = run_part(1, repeat, &part1);
+                    (answer.to_string(), elapsed)
+                }
+                2 => {
+                    let (answer, elapsed) = run_part(2, repeat, &part2);
+                    (answer.to_string(), elapsed)
+                }
+                _ => unreachable!("unknown part {}", part),
+            };
+            (part, answer, elapsed)
+        })
+        .collect::<Vec<_>>();
+
+    match args.format {
+        OutputFormat::Text => match answers.as_slice() {
+            [(_, answer, _)] => println!("{}", answer),
+            _ => {
+                for (part, answer, _) in &answers {
+                    println!("{}", labeled(*part, answer));
+                }
+            }
+        },
+        OutputFormat::Json => {
+            let answer_of = |part| {
+                answers
+                    .iter()
+                    .find(|(p, _, _)| *p == part)
+                    .map(|(_, answer, _)| answer.clone())
+            };
+            let micros_of = |part| {
+                answers
+                    .iter()
+                    .find(|(p, _, _)| *p == part)
+                    .map(|(_, _, d)| d.as_micros() as u64)
+            };
+            let report = SolveReport {
+                day: program_day(),
+                part1: answer_of(1),
+                part2: answer_of(2),
+                ms: answers
+                    .iter()
+                    .map(|(_, _, d)| *d)
+                    .sum::<Duration>()
+                    .as_secs_f64()
+                    * 1000.0,
+                part1_micros: micros_of(1),
+                part2_micros: micros_of(2),
+            };
+            println!("{}", serde_json::to_string(&report).expect("serializable report"));
+        }
+    }
+
+    #[cfg(feature = "allocs")]
+    if args.profile_allocs {
+        let total = allocs::stats();
+        eprintln!(
+            "Allocations: {} ({} bytes) while solving, {} ({} bytes) in total",
+            total.allocations - allocs_before.allocations,
+            total.bytes - allocs_before.bytes,
+            total.allocations,
+            total.bytes
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenge_parts_aliases() {
+        assert_eq!(parse_challenge_parts("all"), Ok(ChallengeParts(vec![1, 2])));
+        assert_eq!(parse_challenge_parts("stage1"), Ok(ChallengeParts(vec![1])));
+        assert_eq!(parse_challenge_parts("stage2"), Ok(ChallengeParts(vec![2])));
+    }
+
+    #[test]
+    fn challenge_parts_lists() {
+        assert_eq!(parse_challenge_parts("1"), Ok(ChallengeParts(vec![1])));
+        assert_eq!(parse_challenge_parts("1,2"), Ok(ChallengeParts(vec![1, 2])));
+        assert_eq!(parse_challenge_parts("2, 1"), Ok(ChallengeParts(vec![2, 1])));
+    }
+
+    #[test]
+    fn challenge_parts_invalid() {
+        assert!(parse_challenge_parts("").is_err());
+        assert!(parse_challenge_parts("1,").is_err());
+        assert!(parse_challenge_parts("3").is_err());
+        assert!(parse_challenge_parts("1,1").is_err());
+        assert!(parse_challenge_parts("stage3").is_err());
+    }
+
+    #[derive(clap::Parser)]
+    struct Cmdline {
+        #[clap(flatten)]
+        runner: RunnerArgs,
+    }
+
+    fn runner_args(args: &[&str]) -> RunnerArgs {
+        <Cmdline as clap::Parser>::try_parse_from(args)
+            .unwrap()
+            .runner
+    }
+
+    #[test]
+    fn print_parsed_short_circuits() {
+        let rendered = std::cell::Cell::new(false);
+        let render = || {
+            rendered.set(true);
+            "parsed".to_owned()
+        };
+
+        assert!(!runner_args(&["dayNN"]).print_parsed(render));
+        assert!(!rendered.get());
+        assert!(runner_args(&["dayNN", "--dump-parsed"]).print_parsed(render));
+        assert!(rendered.get());
+    }
+
+    #[test]
+    fn labeled_single_line() {
+        assert_eq!(labeled(1, 42), "Part 1: 42");
+    }
+
+    #[test]
+    fn labeled_multi_line() {
+        assert_eq!(labeled(2, "#.\n.#"), "Part 2:\n#.\n.#");
+    }
+
+    #[test]
+    fn timings_odd_samples() {
+        let samples = [3, 1, 8].map(Duration::from_millis);
+
+        assert_eq!(
+            Timings::from_samples(&samples),
+            Some(Timings {
+                min: Duration::from_millis(1),
+                median: Duration::from_millis(3),
+                mean: Duration::from_millis(4),
+            })
+        );
+    }
+
+    #[test]
+    fn timings_even_samples() {
+        let samples = [4, 1, 8, 2].map(Duration::from_millis);
+        let timings = Timings::from_samples(&samples).unwrap();
+
+        assert_eq!(timings.median, Duration::from_millis(3));
+        assert_eq!(timings.mean, Duration::from_micros(3750));
+        assert_eq!(Timings::from_samples(&[]), None);
+    }
+
+    #[test]
+    fn run_part_returns_last_answer() {
+        let runs = std::cell::Cell::new(0);
+        let (answer, _) = run_part(1, 3, || {
+            runs.set(runs.get() + 1);
+            runs.get()
+        });
+
+        assert_eq!((answer, runs.get()), (3, 3));
+    }
+
+    #[test]
+    fn day_of_program_name() {
+        assert_eq!(day_of_program(Path::new("target/debug/day07")), Some(7));
+        assert_eq!(day_of_program(Path::new("day11.exe")), Some(11));
+        assert_eq!(day_of_program(Path::new("aoc")), None);
+    }
+
+    #[test]
+    fn solve_report_json() {
+        let report = SolveReport {
+            day: Some(7),
+            part1: Some("95437".to_owned()),
+            part2: None,
+            ms: 1.5,
+            part1_micros: Some(1500),
+            part2_micros: None,
+        };
+        let json = serde_json::to_string(&report).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["day"], 7);
+        assert_eq!(value["part1"], "95437");
+        assert_eq!(value["part2"], serde_json::Value::Null);
+        assert_eq!(value["ms"], 1.5);
+        assert_eq!(value["part1_micros"], 1500);
+        assert_eq!(serde_json::from_str::<SolveReport>(&json).unwrap(), report);
+    }
+
+    #[test]
+    fn solve_report_json_without_micros() {
+        let json = r#"{"day":7,"part1":"95437","part2":null,"ms":1.5}"#;
+        let report = serde_json::from_str::<SolveReport>(json).unwrap();
+
+        assert_eq!((report.part1_micros, report.part2_micros), (None, None));
+    }
+}
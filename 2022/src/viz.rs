@@ -0,0 +1,91 @@
+//! Terminal colors for the renderers, enabled with the `viz` feature.
+//!
+//! Without the feature, text is returned unchanged so that the output stays plain ASCII.
+
+#[cfg(feature = "viz")]
+use owo_colors::OwoColorize;
+
+/// The role of a piece of rendered text, which determines its color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Style {
+    /// Something to look at: a lit pixel, a visible tree, a visited cell.
+    Lit,
+    /// Something in the background: an unlit pixel, a hidden tree.
+    Dim,
+    /// A directory in a filesystem tree.
+    Directory,
+    /// A file in a filesystem tree.
+    File,
+}
+
+/// Returns `text` colored according to `style`.
+#[cfg(feature = "viz")]
+pub fn paint(text: &str, style: Style) -> String {
+    match style {
+        Style::Lit => text.bright_yellow().bold().to_string(),
+        Style::Dim => text.dimmed().to_string(),
+        Style::Directory => text.bright_blue().bold().to_string(),
+        Style::File => text.green().to_string(),
+    }
+}
+
+/// Returns `text` unchanged, colors being disabled.
+#[cfg(not(feature = "viz"))]
+pub fn paint(text: &str, _style: Style) -> String {
+    text.to_owned()
+}
+
+/// Colors each character of `text` according to `style_of`. Characters without a style, such as
+/// newlines, are left as-is.
+pub fn paint_chars(text: &str, style_of: impl Fn(char) -> Option<Style>) -> String {
+    text.chars()
+        .map(|c| match style_of(c) {
+            Some(style) => paint(&c.to_string(), style),
+            None => c.to_string(),
+        })
+        .collect()
+}
+
+/// The style of a pixel of a `#`/`.` drawing.
+pub fn pixel_style(c: char) -> Option<Style> {
+    match c {
+        '#' => Some(Style::Lit),
+        '.' => Some(Style::Dim),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "viz"))]
+    #[test]
+    fn plain_output_is_unchanged() {
+        let drawing = "##..\n.#.#";
+
+        assert_eq!(paint(drawing, Style::Lit), drawing);
+        assert_eq!(paint_chars(drawing, pixel_style), drawing);
+    }
+
+    #[cfg(feature = "viz")]
+    #[test]
+    fn colored_output_preserves_text() {
+        let drawing = "##..\n.#.#";
+        let painted = paint_chars(drawing, pixel_style);
+
+        // Strip the ANSI escape sequences, of the form `ESC [ ... m`.
+        let mut stripped = String::new();
+        let mut chars = painted.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|c| *c == 'm');
+            } else {
+                stripped.push(c);
+            }
+        }
+
+        assert_ne!(painted, drawing);
+        assert_eq!(stripped, drawing);
+    }
+}